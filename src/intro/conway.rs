@@ -1,44 +1,93 @@
 #![forbid(unsafe_code)]
 
+pub mod active;
+pub mod census;
+pub mod elementary;
+pub mod history;
+pub mod path;
+pub mod predecessor;
+pub mod rule;
+pub mod run;
+pub mod soup;
+pub mod storage;
+pub mod turmite;
+
+use active::ActiveRegion;
+use history::History;
+use rule::Rule;
+use std::hash::{Hash, Hasher};
+use std::marker::PhantomData;
+use storage::{Dense, Storage};
+
 ////////////////////////////////////////////////////////////////////////////////
 
-#[derive(Clone, PartialEq, Eq)]
-pub struct Grid<T> {
+#[derive(Clone)]
+pub struct Grid<T, S = Dense<T>> {
     rows: usize,
     cols: usize,
-    grid: Vec<T>,
+    grid: S,
+    cells: PhantomData<T>,
 }
 
 impl<T: Clone + Default> Grid<T> {
     pub fn new(rows: usize, cols: usize) -> Self {
-        Grid {
-            grid: vec![T::default(); rows * cols],
-            rows,
-            cols,
-        }
+        Self::with_storage(rows, cols)
     }
 
     pub fn from_slice(grid: &[T], rows: usize, cols: usize) -> Self {
         if grid.len() != rows * cols {
             panic!("Неверный размер");
         }
+        let mut res = Self::new(rows, cols);
+        for (i, value) in grid.iter().enumerate() {
+            res.set(value.clone(), i / cols, i % cols);
+        }
+        res
+    }
+}
+
+impl<T: Clone + Default, S: Storage<T>> Grid<T, S> {
+    pub fn with_storage(rows: usize, cols: usize) -> Self {
         Grid {
-            grid: grid.to_vec(),
+            grid: S::new(rows, cols),
             rows,
             cols,
+            cells: PhantomData,
         }
     }
 
+    pub fn storage(&self) -> &S {
+        &self.grid
+    }
+
+    pub fn to_storage<R: Storage<T>>(&self) -> Grid<T, R> {
+        let mut res = Grid::with_storage(self.rows, self.cols);
+        for row in 0..self.rows {
+            for col in 0..self.cols {
+                res.set(self.get(row, col).clone(), row, col);
+            }
+        }
+        res
+    }
+
     pub fn size(&self) -> (usize, usize) {
         (self.cols, self.rows)
     }
 
     pub fn get(&self, row: usize, col: usize) -> &T {
-        &self.grid[col + row * self.cols]
+        self.check(row, col);
+        self.grid.get(row, col)
     }
 
     pub fn set(&mut self, value: T, row: usize, col: usize) {
-        self.grid[col + row * self.cols] = value;
+        self.check(row, col);
+        self.grid.set(value, row, col);
+    }
+
+    fn check(&self, row: usize, col: usize) {
+        if row >= self.rows || col >= self.cols {
+            panic!("Выход за границы поля");
+        }
     }
 
     pub fn neighbours(&self, row: usize, col: usize) -> impl Iterator<Item = (usize, usize)> + '_ {
@@ -61,9 +110,35 @@ impl<T: Clone + Default> Grid<T> {
     }
 }
 
+// Grids are compared cell by cell, so the same cells kept by different
+// backends (or by the same backend in a different layout) are equal.
+impl<T: PartialEq, S: Storage<T>> PartialEq for Grid<T, S> {
+    fn eq(&self, other: &Self) -> bool {
+        self.rows == other.rows
+            && self.cols == other.cols
+            && (0..self.rows).all(|row| {
+                (0..self.cols).all(|col| self.grid.get(row, col) == other.grid.get(row, col))
+            })
+    }
+}
+
+impl<T: Eq, S: Storage<T>> Eq for Grid<T, S> {}
+
+impl<T: Hash, S: Storage<T>> Hash for Grid<T, S> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.rows.hash(state);
+        self.cols.hash(state);
+        for row in 0..self.rows {
+            for col in 0..self.cols {
+                self.grid.get(row, col).hash(state);
+            }
+        }
+    }
+}
+
 ////////////////////////////////////////////////////////////////////////////////
 
-#[derive(Clone, Copy, PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 pub enum Cell {
     Dead,
     Alive,
@@ -77,43 +152,125 @@ impl Default for Cell {
 
 ////////////////////////////////////////////////////////////////////////////////
 
-#[derive(PartialEq, Eq)]
-pub struct GameOfLife {
-    grid: Grid<Cell>,
+pub struct GameOfLife<S = Dense<Cell>> {
+    grid: Grid<Cell, S>,
+    generation: usize,
+    history: Option<History<S>>,
+    active: Option<ActiveRegion>,
+    rule: Rule,
 }
 
 impl GameOfLife {
     pub fn from_grid(grid: Grid<Cell>) -> Self {
-        GameOfLife { grid }
+        Self::with_storage(grid)
+    }
+}
+
+impl<S: Storage<Cell> + Clone> GameOfLife<S> {
+    pub fn with_storage(grid: Grid<Cell, S>) -> Self {
+        GameOfLife {
+            grid,
+            generation: 0,
+            history: None,
+            active: None,
+            rule: Rule::default(),
+        }
     }
 
-    pub fn get_grid(&self) -> &Grid<Cell> {
+    pub fn get_grid(&self) -> &Grid<Cell, S> {
         &self.grid
     }
 
+    pub fn generation(&self) -> usize {
+        self.generation
+    }
+
     pub fn step(&mut self) {
-        let mut next = Grid::new(self.grid.rows, self.grid.cols);
-        for x in 0..self.grid.rows {
-            for y in 0..self.grid.cols {
-                let count = self
-                    .grid
-                    .neighbours(x, y)
-                    .map(|n| self.grid.get(n.0, n.1))
-                    .filter(|n| **n == Cell::Alive)
-                    .count();
-                let cell = self.grid.get(x, y);
-                next.set(
-                    match (cell, count) {
-                        (c, 2) => *c,
-                        (_, 3) => Cell::Alive,
-                        _ => Cell::Dead,
-                    },
-                    x,
-                    y,
-                )
+        self.advance();
+    }
+
+    // Moves to the next generation, returning the cells that have changed.
+    fn advance(&mut self) -> Vec<(usize, usize)> {
+        if let Some(diff) = self.replay_step() {
+            return diff;
+        }
+        let changed: Vec<(usize, usize)> = match &self.active {
+            Some(active) if !active.saturated() && !self.rule.births_on_empty() => active
+                .cells()
+                .filter(|&(x, y)| self.next_cell(x, y) != *self.grid.get(x, y))
+                .collect(),
+            _ if S::SPARSE && !self.rule.births_on_empty() => self
+                .around_alive()
+                .into_iter()
+                .filter(|&(x, y)| self.next_cell(x, y) != *self.grid.get(x, y))
+                .collect(),
+            _ => (0..self.grid.rows)
+                .flat_map(|x| (0..self.grid.cols).map(move |y| (x, y)))
+                .filter(|&(x, y)| self.next_cell(x, y) != *self.grid.get(x, y))
+                .collect(),
+        };
+        flip(&mut self.grid, &changed);
+        self.generation += 1;
+        if let Some(active) = &mut self.active {
+            active.mark(&changed);
+        }
+        if let Some(history) = &mut self.history {
+            history.record(changed.clone(), &self.grid, self.generation);
+        }
+        changed
+    }
+
+    // Without births on empty neighbourhoods, only these cells may change.
+    fn around_alive(&self) -> Vec<(usize, usize)> {
+        let mut res: Vec<_> = self
+            .grid
+            .storage()
+            .cells()
+            .filter(|(_, cell)| **cell == Cell::Alive)
+            .flat_map(|((x, y), _)| self.grid.neighbours(x, y).chain([(x, y)]))
+            .collect();
+        res.sort_unstable();
+        res.dedup();
+        res
+    }
+
+    fn next_cell(&self, x: usize, y: usize) -> Cell {
+        let mut index = 0;
+        for (i, row) in (x as isize - 1..=x as isize + 1).enumerate() {
+            for (j, col) in (y as isize - 1..=y as isize + 1).enumerate() {
+                let inside = row >= 0
+                    && col >= 0
+                    && row < self.grid.rows as isize
+                    && col < self.grid.cols as isize;
+                if inside && *self.grid.get(row as usize, col as usize) == Cell::Alive {
+                    index |= 1 << (3 * i + j);
+                }
             }
         }
-        self.grid = next;
+        self.rule.next(index)
+    }
+}
+
+// Not derived, which would require `S: PartialEq`.
+impl<S: Storage<Cell>> PartialEq for GameOfLife<S> {
+    fn eq(&self, other: &Self) -> bool {
+        self.grid == other.grid
+            && self.generation == other.generation
+            && self.history == other.history
+            && self.active == other.active
+            && self.rule == other.rule
+    }
+}
+
+impl<S: Storage<Cell>> Eq for GameOfLife<S> {}
+
+fn flip<S: Storage<Cell>>(grid: &mut Grid<Cell, S>, cells: &[(usize, usize)]) {
+    for &(row, col) in cells {
+        let cell = match grid.get(row, col) {
+            Cell::Alive => Cell::Dead,
+            Cell::Dead => Cell::Alive,
+        };
+        grid.set(cell, row, col);
     }
 }
 
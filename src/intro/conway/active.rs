@@ -0,0 +1,212 @@
+#![forbid(unsafe_code)]
+
+use super::storage::Storage;
+use super::{Cell, GameOfLife};
+use std::collections::HashSet;
+
+////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ActiveConfig {
+    // The board is split into square tiles of this side, and only tiles next
+    // to a change from the previous generation are recomputed.
+    pub tile_size: usize,
+    // Once more than this percentage of tiles is dirty, the whole board is
+    // swept as usual, which is cheaper than walking the tiles one by one.
+    pub full_sweep_percent: usize,
+}
+
+impl Default for ActiveConfig {
+    fn default() -> Self {
+        ActiveConfig {
+            tile_size: 16,
+            full_sweep_percent: 50,
+        }
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+// Dirty tiles are kept in a set, so that a huge sparse board does not need a
+// flag for every tile.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(super) struct ActiveRegion {
+    config: ActiveConfig,
+    rows: usize,
+    cols: usize,
+    tiles: usize,
+    dirty: HashSet<(usize, usize)>,
+    // Every tile is dirty, without listing them.
+    all: bool,
+}
+
+impl ActiveRegion {
+    fn new(config: ActiveConfig, rows: usize, cols: usize) -> Self {
+        let tile_size = config.tile_size.max(1);
+        ActiveRegion {
+            config: ActiveConfig {
+                tile_size,
+                ..config
+            },
+            rows,
+            cols,
+            tiles: rows
+                .div_ceil(tile_size)
+                .saturating_mul(cols.div_ceil(tile_size)),
+            dirty: HashSet::new(),
+            all: true,
+        }
+    }
+
+    pub(super) fn saturated(&self) -> bool {
+        self.all
+            || self.dirty.len().saturating_mul(100)
+                > self.config.full_sweep_percent.saturating_mul(self.tiles)
+    }
+
+    // Only called when not saturated.
+    pub(super) fn cells(&self) -> impl Iterator<Item = (usize, usize)> + '_ {
+        let size = self.config.tile_size;
+        self.dirty.iter().flat_map(move |&(tile_row, tile_col)| {
+            let rows = tile_row * size..((tile_row + 1) * size).min(self.rows);
+            let cols = tile_col * size..((tile_col + 1) * size).min(self.cols);
+            rows.flat_map(move |row| cols.clone().map(move |col| (row, col)))
+        })
+    }
+
+    // Only cells around the changed ones may change in the next generation.
+    pub(super) fn mark(&mut self, changed: &[(usize, usize)]) {
+        self.dirty.clear();
+        self.all = false;
+        let size = self.config.tile_size;
+        for &(row, col) in changed {
+            let tile_rows = row.saturating_sub(1) / size..=((row + 1).min(self.rows - 1)) / size;
+            for tile_row in tile_rows {
+                let tile_cols =
+                    col.saturating_sub(1) / size..=((col + 1).min(self.cols - 1)) / size;
+                for tile_col in tile_cols {
+                    self.dirty.insert((tile_row, tile_col));
+                }
+            }
+        }
+    }
+
+    pub(super) fn mark_all(&mut self) {
+        self.dirty.clear();
+        self.all = true;
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+impl<S: Storage<Cell> + Clone> GameOfLife<S> {
+    pub fn enable_active_region(&mut self, config: ActiveConfig) {
+        self.active = Some(ActiveRegion::new(config, self.grid.rows, self.grid.cols));
+    }
+
+    pub fn disable_active_region(&mut self) {
+        self.active = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ActiveConfig;
+    use crate::intro::conway::census::cells_from_rows;
+    use crate::intro::conway::history::HistoryConfig;
+    use crate::intro::conway::{Cell, GameOfLife, Grid};
+    use rand::Rng;
+
+    fn random_grid(rows: usize, cols: usize, density: u32) -> Grid<Cell> {
+        let mut rng = rand::thread_rng();
+        let cells: Vec<Cell> = (0..rows * cols)
+            .map(|_| {
+                if rng.gen_range(0..100) < density {
+                    Cell::Alive
+                } else {
+                    Cell::Dead
+                }
+            })
+            .collect();
+        Grid::from_slice(&cells, rows, cols)
+    }
+
+    fn assert_same_evolution(grid: Grid<Cell>, config: ActiveConfig, generations: usize) {
+        let mut plain = GameOfLife::from_grid(grid.clone());
+        let mut game = GameOfLife::from_grid(grid);
+        game.enable_active_region(config);
+        for _ in 0..generations {
+            plain.step();
+            game.step();
+            assert!(plain.get_grid() == game.get_grid());
+        }
+    }
+
+    #[test]
+    fn matches_full_sweep() {
+        for tile_size in [1, 2, 3, 5, 8, 16, 100] {
+            for percent in [0, 30, 100] {
+                let config = ActiveConfig {
+                    tile_size,
+                    full_sweep_percent: percent,
+                };
+                assert_same_evolution(random_grid(23, 31, 35), config, 60);
+                assert_same_evolution(random_grid(7, 1, 50), config, 10);
+                assert_same_evolution(random_grid(1, 9, 50), config, 10);
+            }
+        }
+    }
+
+    #[test]
+    fn only_changes_are_recomputed() {
+        let mut grid = Grid::new(64, 64);
+        for (row, col) in cells_from_rows(&[".O.", "..O", "OOO"]) {
+            grid.set(Cell::Alive, row + 1, col + 1);
+        }
+        for (row, col) in cells_from_rows(&["OO", "OO"]) {
+            grid.set(Cell::Alive, row + 40, col + 10);
+        }
+        let mut game = GameOfLife::from_grid(grid.clone());
+        game.enable_active_region(ActiveConfig {
+            tile_size: 8,
+            full_sweep_percent: 50,
+        });
+        game.step();
+        for _ in 0..50 {
+            game.step();
+            let active = game.active.as_ref().unwrap();
+            assert!(!active.saturated());
+            assert!(active.dirty.len() <= 4);
+        }
+        assert_same_evolution(
+            grid,
+            ActiveConfig {
+                tile_size: 8,
+                full_sweep_percent: 50,
+            },
+            150,
+        );
+    }
+
+    #[test]
+    fn works_with_history() {
+        let grid = random_grid(30, 30, 30);
+        let mut plain = GameOfLife::from_grid(grid.clone());
+        let mut game = GameOfLife::from_grid(grid);
+        game.enable_active_region(ActiveConfig::default());
+        game.enable_history(HistoryConfig::default());
+        let mut expected = vec![plain.get_grid().clone()];
+        for _ in 0..40 {
+            plain.step();
+            expected.push(plain.get_grid().clone());
+        }
+        game.goto(40).unwrap();
+        game.goto(10).unwrap();
+        assert!(game.get_grid() == &expected[10]);
+        game.disable_history();
+        for grid in &expected[11..] {
+            game.step();
+            assert!(game.get_grid() == grid);
+        }
+    }
+}
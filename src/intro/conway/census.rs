@@ -0,0 +1,483 @@
+#![forbid(unsafe_code)]
+
+use super::storage::{Sparse, Storage};
+use super::{Cell, GameOfLife, Grid};
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+use std::sync::OnceLock;
+
+////////////////////////////////////////////////////////////////////////////////
+
+// Live cells closer than this (in Chebyshev distance) belong to the same object.
+// With a distance of 1 some oscillators (beacon, pulsar) fall apart into pieces.
+const LINK_DISTANCE: isize = 2;
+
+// The longest period `Catalogue::register` looks for.
+const MAX_PERIOD: usize = 30;
+
+////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum ObjectKind {
+    StillLife,
+    Oscillator { period: usize },
+    Spaceship { period: usize },
+    Unknown,
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+type Transform = fn((isize, isize)) -> (isize, isize);
+
+// Live cells of an object shifted to the origin and brought to the smallest of
+// its 8 rotations and reflections, so equal objects have equal shapes.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Shape {
+    cells: Vec<(usize, usize)>,
+}
+
+impl Shape {
+    pub fn from_cells(cells: &[(usize, usize)]) -> Self {
+        let cells: Vec<(isize, isize)> = cells
+            .iter()
+            .map(|&(row, col)| (row as isize, col as isize))
+            .collect();
+        let transforms: [Transform; 8] = [
+            |(r, c)| (r, c),
+            |(r, c)| (r, -c),
+            |(r, c)| (-r, c),
+            |(r, c)| (-r, -c),
+            |(r, c)| (c, r),
+            |(r, c)| (c, -r),
+            |(r, c)| (-c, r),
+            |(r, c)| (-c, -r),
+        ];
+        let cells = transforms
+            .iter()
+            .map(|transform| {
+                let moved: Vec<(isize, isize)> = cells.iter().map(|&p| transform(p)).collect();
+                normalize(&moved)
+            })
+            .min()
+            .unwrap_or_default();
+        Shape { cells }
+    }
+
+    pub fn cells(&self) -> &[(usize, usize)] {
+        &self.cells
+    }
+
+    pub fn population(&self) -> usize {
+        self.cells.len()
+    }
+}
+
+fn normalize(cells: &[(isize, isize)]) -> Vec<(usize, usize)> {
+    let min_row = cells.iter().map(|p| p.0).min().unwrap_or(0);
+    let min_col = cells.iter().map(|p| p.1).min().unwrap_or(0);
+    let mut res: Vec<(usize, usize)> = cells
+        .iter()
+        .map(|&(row, col)| ((row - min_row) as usize, (col - min_col) as usize))
+        .collect();
+    res.sort_unstable();
+    res
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Object {
+    pub cells: Vec<(usize, usize)>,
+    pub shape: Shape,
+    pub name: Option<String>,
+    pub kind: ObjectKind,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Census {
+    objects: Vec<Object>,
+    counts: BTreeMap<String, usize>,
+}
+
+impl Census {
+    pub fn objects(&self) -> &[Object] {
+        &self.objects
+    }
+
+    // Number of objects per name, unidentified ones are counted as "unknown".
+    pub fn counts(&self) -> &BTreeMap<String, usize> {
+        &self.counts
+    }
+
+    pub fn count(&self, name: &str) -> usize {
+        self.counts.get(name).copied().unwrap_or(0)
+    }
+
+    pub fn len(&self) -> usize {
+        self.objects.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.objects.is_empty()
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Clone, Debug, Default)]
+pub struct Catalogue {
+    known: HashMap<Shape, (String, ObjectKind)>,
+}
+
+impl Catalogue {
+    pub fn new() -> Self {
+        Catalogue {
+            known: HashMap::new(),
+        }
+    }
+
+    pub fn standard() -> &'static Catalogue {
+        static STANDARD: OnceLock<Catalogue> = OnceLock::new();
+        STANDARD.get_or_init(|| {
+            let mut catalogue = Catalogue::new();
+            for (name, rows) in STANDARD_OBJECTS {
+                catalogue.register(name, rows);
+            }
+            catalogue
+        })
+    }
+
+    // Evolves the pattern in isolation to find out what it is and remembers
+    // every phase of it. Patterns that are not periodic are not registered.
+    pub fn register(&mut self, name: &str, rows: &[&str]) -> ObjectKind {
+        let cells = cells_from_rows(rows);
+        let (phases, kind) = evolve(&cells);
+        if kind != ObjectKind::Unknown {
+            for phase in phases {
+                self.known
+                    .entry(Shape::from_cells(&phase))
+                    .or_insert_with(|| (name.to_string(), kind));
+            }
+        }
+        kind
+    }
+
+    pub fn identify(&self, shape: &Shape) -> Option<(&str, ObjectKind)> {
+        self.known
+            .get(shape)
+            .map(|(name, kind)| (name.as_str(), *kind))
+    }
+
+    pub fn census<S: Storage<Cell>>(&self, grid: &Grid<Cell, S>) -> Census {
+        let mut census = Census::default();
+        for cells in components(grid) {
+            let shape = Shape::from_cells(&cells);
+            let (name, kind) = match self.identify(&shape) {
+                Some((name, kind)) => (Some(name.to_string()), kind),
+                None => (None, ObjectKind::Unknown),
+            };
+            let key = name.clone().unwrap_or_else(|| "unknown".to_string());
+            *census.counts.entry(key).or_insert(0) += 1;
+            census.objects.push(Object {
+                cells,
+                shape,
+                name,
+                kind,
+            });
+        }
+        census
+    }
+}
+
+const STANDARD_OBJECTS: [(&str, &[&str]); 17] = [
+    ("block", &["OO", "OO"]),
+    ("beehive", &[".OO.", "O..O", ".OO."]),
+    ("loaf", &[".OO.", "O..O", ".O.O", "..O."]),
+    ("boat", &["OO.", "O.O", ".O."]),
+    ("ship", &["OO.", "O.O", ".OO"]),
+    ("tub", &[".O.", "O.O", ".O."]),
+    ("pond", &[".OO.", "O..O", "O..O", ".OO."]),
+    ("long boat", &["OO..", "O.O.", ".O.O", "..O."]),
+    ("blinker", &["OOO"]),
+    ("toad", &[".OOO", "OOO."]),
+    ("beacon", &["OO..", "OO..", "..OO", "..OO"]),
+    (
+        "pulsar",
+        &[
+            "..OOO...OOO..",
+            ".............",
+            "O....O.O....O",
+            "O....O.O....O",
+            "O....O.O....O",
+            "..OOO...OOO..",
+            ".............",
+            "..OOO...OOO..",
+            "O....O.O....O",
+            "O....O.O....O",
+            "O....O.O....O",
+            ".............",
+            "..OOO...OOO..",
+        ],
+    ),
+    (
+        "pentadecathlon",
+        &["..O....O..", "OO.OOOO.OO", "..O....O.."],
+    ),
+    ("glider", &[".O.", "..O", "OOO"]),
+    ("lwss", &[".O..O", "O....", "O...O", "OOOO."]),
+    ("mwss", &["...O..", ".O...O", "O.....", "O....O", "OOOOO."]),
+    (
+        "hwss",
+        &["...OO..", ".O....O", "O......", "O.....O", "OOOOOO."],
+    ),
+];
+
+////////////////////////////////////////////////////////////////////////////////
+
+impl<S: Storage<Cell> + Clone> GameOfLife<S> {
+    pub fn census(&self) -> Census {
+        Catalogue::standard().census(&self.grid)
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+// Live cells of the rows, where 'O' (or '*') is alive and anything else is dead.
+pub(crate) fn cells_from_rows(rows: &[&str]) -> Vec<(usize, usize)> {
+    rows.iter()
+        .enumerate()
+        .flat_map(|(row, line)| {
+            line.chars()
+                .enumerate()
+                .filter(|(_, ch)| *ch == 'O' || *ch == '*')
+                .map(move |(col, _)| (row, col))
+        })
+        .collect()
+}
+
+// In row-major order.
+fn live_cells<S: Storage<Cell>>(grid: &Grid<Cell, S>) -> Vec<(usize, usize)> {
+    let mut res: Vec<_> = grid
+        .storage()
+        .cells()
+        .filter(|(_, cell)| **cell == Cell::Alive)
+        .map(|(position, _)| position)
+        .collect();
+    res.sort_unstable();
+    res
+}
+
+fn components<S: Storage<Cell>>(grid: &Grid<Cell, S>) -> Vec<Vec<(usize, usize)>> {
+    let mut visited = Grid::<bool, Sparse<bool>>::with_storage(grid.rows, grid.cols);
+    let mut res = vec![];
+    for (row, col) in live_cells(grid) {
+        if *visited.get(row, col) {
+            continue;
+        }
+        visited.set(true, row, col);
+        let mut component = vec![];
+        let mut queue = VecDeque::from([(row, col)]);
+        while let Some((row, col)) = queue.pop_front() {
+            component.push((row, col));
+            for dr in -LINK_DISTANCE..=LINK_DISTANCE {
+                for dc in -LINK_DISTANCE..=LINK_DISTANCE {
+                    let (r, c) = (row as isize + dr, col as isize + dc);
+                    if r < 0 || c < 0 || r >= grid.rows as isize || c >= grid.cols as isize {
+                        continue;
+                    }
+                    let (r, c) = (r as usize, c as usize);
+                    if *grid.get(r, c) == Cell::Alive && !*visited.get(r, c) {
+                        visited.set(true, r, c);
+                        queue.push_back((r, c));
+                    }
+                }
+            }
+        }
+        component.sort_unstable();
+        res.push(component);
+    }
+    res
+}
+
+// Runs the cells alone on a board large enough that nothing reaches the edge
+// within `MAX_PERIOD` generations, returning the phases seen before the
+// pattern repeated itself.
+fn evolve(cells: &[(usize, usize)]) -> (Vec<Vec<(usize, usize)>>, ObjectKind) {
+    let height = cells.iter().map(|p| p.0 + 1).max().unwrap_or(0);
+    let width = cells.iter().map(|p| p.1 + 1).max().unwrap_or(0);
+    let padding = MAX_PERIOD + 2;
+    let mut grid = Grid::new(height + 2 * padding, width + 2 * padding);
+    for &(row, col) in cells {
+        grid.set(Cell::Alive, row + padding, col + padding);
+    }
+    let mut game = GameOfLife::from_grid(grid);
+
+    let start = live_cells(&game.grid);
+    let start_set: HashSet<(usize, usize)> = start.iter().copied().collect();
+    let start_shape = translated(&start);
+    let mut phases = vec![start];
+    for period in 1..=MAX_PERIOD {
+        game.step();
+        let current = live_cells(&game.grid);
+        if current.is_empty() {
+            break;
+        }
+        if translated(&current) == start_shape {
+            let kind = if current.iter().all(|p| start_set.contains(p)) {
+                if period == 1 {
+                    ObjectKind::StillLife
+                } else {
+                    ObjectKind::Oscillator { period }
+                }
+            } else {
+                ObjectKind::Spaceship { period }
+            };
+            return (phases, kind);
+        }
+        phases.push(current);
+    }
+    (phases, ObjectKind::Unknown)
+}
+
+fn translated(cells: &[(usize, usize)]) -> Vec<(usize, usize)> {
+    let cells: Vec<(isize, isize)> = cells
+        .iter()
+        .map(|&(row, col)| (row as isize, col as isize))
+        .collect();
+    normalize(&cells)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{cells_from_rows, Catalogue, ObjectKind, Shape, STANDARD_OBJECTS};
+    use crate::intro::conway::{Cell, GameOfLife, Grid};
+
+    fn place(grid: &mut Grid<Cell>, rows: &[&str], row: usize, col: usize) {
+        for (r, c) in cells_from_rows(rows) {
+            grid.set(Cell::Alive, row + r, col + c);
+        }
+    }
+
+    #[test]
+    fn shape_is_symmetry_invariant() {
+        let glider = Shape::from_cells(&cells_from_rows(&[".O.", "..O", "OOO"]));
+        let mirrored = Shape::from_cells(&cells_from_rows(&[".O.", "O..", "OOO"]));
+        let rotated = Shape::from_cells(&cells_from_rows(&["OO.", "O.O", "O.."]));
+        let shifted = Shape::from_cells(&[(10, 11), (11, 12), (12, 10), (12, 11), (12, 12)]);
+        assert_eq!(glider, mirrored);
+        assert_eq!(glider, rotated);
+        assert_eq!(glider, shifted);
+        assert_eq!(glider.population(), 5);
+        assert_ne!(glider, Shape::from_cells(&cells_from_rows(&["OOO"])));
+    }
+
+    #[test]
+    fn standard_kinds() {
+        let catalogue = Catalogue::standard();
+        let kind = |rows: &[&str]| {
+            catalogue
+                .identify(&Shape::from_cells(&cells_from_rows(rows)))
+                .map(|(_, kind)| kind)
+        };
+        assert_eq!(kind(&["OO", "OO"]), Some(ObjectKind::StillLife));
+        assert_eq!(kind(&["OOO"]), Some(ObjectKind::Oscillator { period: 2 }));
+        assert_eq!(
+            kind(&["O", "O", "O"]),
+            Some(ObjectKind::Oscillator { period: 2 })
+        );
+        assert_eq!(
+            kind(&[".O.", "..O", "OOO"]),
+            Some(ObjectKind::Spaceship { period: 4 })
+        );
+        assert_eq!(
+            kind(&["O.O", ".OO", ".O."]),
+            Some(ObjectKind::Spaceship { period: 4 })
+        );
+        assert_eq!(kind(&["OO", "O."]), None);
+
+        for (name, rows) in STANDARD_OBJECTS {
+            let (found, kind) = catalogue
+                .identify(&Shape::from_cells(&cells_from_rows(rows)))
+                .unwrap();
+            assert_eq!(found, name);
+            assert_ne!(kind, ObjectKind::Unknown);
+        }
+        let rows = |name: &str| STANDARD_OBJECTS.iter().find(|o| o.0 == name).unwrap().1;
+        assert_eq!(
+            kind(rows("pulsar")),
+            Some(ObjectKind::Oscillator { period: 3 })
+        );
+        assert_eq!(
+            kind(rows("pentadecathlon")),
+            Some(ObjectKind::Oscillator { period: 15 })
+        );
+        assert_eq!(
+            kind(rows("hwss")),
+            Some(ObjectKind::Spaceship { period: 4 })
+        );
+    }
+
+    #[test]
+    fn register_rejects_aperiodic() {
+        let mut catalogue = Catalogue::new();
+        assert_eq!(
+            catalogue.register("r-pentomino", &[".OO", "OO.", ".O."]),
+            ObjectKind::Unknown
+        );
+        assert_eq!(catalogue.register("domino", &["OO"]), ObjectKind::Unknown);
+        assert_eq!(
+            catalogue.register("block", &["OO", "OO"]),
+            ObjectKind::StillLife
+        );
+        assert!(catalogue
+            .identify(&Shape::from_cells(&cells_from_rows(&[".OO", "OO.", ".O."])))
+            .is_none());
+    }
+
+    #[test]
+    fn census_counts() {
+        let mut grid = Grid::new(20, 20);
+        place(&mut grid, &["OO", "OO"], 1, 1);
+        place(&mut grid, &["OO", "OO"], 1, 10);
+        place(&mut grid, &["O", "O", "O"], 6, 1);
+        place(&mut grid, &[".OO.", "O..O", ".OO."], 6, 10);
+        place(&mut grid, &["O.O", ".OO", ".O."], 12, 2);
+        place(&mut grid, &["OO", "O."], 17, 2);
+        place(&mut grid, &["OO..", "O...", "...O", "..OO"], 12, 12);
+
+        let census = Catalogue::standard().census(&grid);
+        assert_eq!(census.len(), 7);
+        assert_eq!(census.count("block"), 2);
+        assert_eq!(census.count("blinker"), 1);
+        assert_eq!(census.count("beehive"), 1);
+        assert_eq!(census.count("glider"), 1);
+        assert_eq!(census.count("beacon"), 1);
+        assert_eq!(census.count("unknown"), 1);
+        assert_eq!(census.count("pond"), 0);
+
+        let unknown = census
+            .objects()
+            .iter()
+            .find(|object| object.name.is_none())
+            .unwrap();
+        assert_eq!(unknown.cells, vec![(17, 2), (17, 3), (18, 2)]);
+        assert_eq!(unknown.kind, ObjectKind::Unknown);
+    }
+
+    #[test]
+    fn census_follows_generations() {
+        let mut grid = Grid::new(16, 16);
+        place(&mut grid, &[".O.", "..O", "OOO"], 0, 0);
+        place(&mut grid, &["OO", "OO"], 13, 1);
+        place(&mut grid, &["OOO"], 2, 11);
+        let mut game = GameOfLife::from_grid(grid);
+        for generation in 1..=8 {
+            game.step();
+            let census = game.census();
+            assert_eq!(census.count("glider"), 1, "generation {}", generation);
+            assert_eq!(census.count("block"), 1, "generation {}", generation);
+            assert_eq!(census.count("blinker"), 1, "generation {}", generation);
+            assert_eq!(census.len(), 3);
+        }
+        assert!(Catalogue::standard().census(&Grid::new(3, 3)).is_empty());
+    }
+}
@@ -0,0 +1,205 @@
+#![forbid(unsafe_code)]
+
+use super::{Cell, Grid};
+
+////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Boundary {
+    Wrap,
+    // Cells beyond the ends always have this state.
+    Fixed(Cell),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Rule {
+    // Wolfram code: bit `4 * left + 2 * center + right` is the next state.
+    Elementary(u8),
+    // Bit `k` of the code is the next state of a cell with `k` live cells
+    // among itself and `radius` neighbours on either side.
+    Totalistic { radius: usize, code: u64 },
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ElementaryAutomaton {
+    rule: Rule,
+    boundary: Boundary,
+}
+
+impl ElementaryAutomaton {
+    pub fn new(rule: u8) -> Self {
+        ElementaryAutomaton {
+            rule: Rule::Elementary(rule),
+            boundary: Boundary::Wrap,
+        }
+    }
+
+    pub fn totalistic(radius: usize, code: u64) -> Self {
+        if radius == 0 || 2 * radius + 2 > u64::BITS as usize {
+            panic!("Неверный радиус");
+        }
+        if 2 * radius + 2 < u64::BITS as usize && code >> (2 * radius + 2) != 0 {
+            panic!("Неверный код правила");
+        }
+        ElementaryAutomaton {
+            rule: Rule::Totalistic { radius, code },
+            boundary: Boundary::Wrap,
+        }
+    }
+
+    pub fn with_boundary(mut self, boundary: Boundary) -> Self {
+        self.boundary = boundary;
+        self
+    }
+
+    pub fn rule(&self) -> Rule {
+        self.rule
+    }
+
+    pub fn radius(&self) -> usize {
+        match self.rule {
+            Rule::Elementary(_) => 1,
+            Rule::Totalistic { radius, .. } => radius,
+        }
+    }
+
+    pub fn next(&self, row: &[Cell]) -> Vec<Cell> {
+        let radius = self.radius() as isize;
+        (0..row.len() as isize)
+            .map(|col| {
+                let window = (col - radius..=col + radius).map(|i| self.cell(row, i));
+                let alive = match self.rule {
+                    Rule::Elementary(rule) => {
+                        let index =
+                            window.fold(0, |acc, cell| 2 * acc + (cell == Cell::Alive) as u8);
+                        rule >> index & 1 == 1
+                    }
+                    Rule::Totalistic { code, .. } => {
+                        let count = window.filter(|&cell| cell == Cell::Alive).count();
+                        code >> count & 1 == 1
+                    }
+                };
+                if alive {
+                    Cell::Alive
+                } else {
+                    Cell::Dead
+                }
+            })
+            .collect()
+    }
+
+    fn cell(&self, row: &[Cell], col: isize) -> Cell {
+        if col >= 0 && col < row.len() as isize {
+            return row[col as usize];
+        }
+        match self.boundary {
+            Boundary::Wrap => row[col.rem_euclid(row.len() as isize) as usize],
+            Boundary::Fixed(cell) => cell,
+        }
+    }
+
+    // Space-time diagram: row `t` of the grid is generation `t`, starting
+    // with `initial` in the first row.
+    pub fn diagram(&self, initial: &[Cell], generations: usize) -> Grid<Cell> {
+        let mut grid = Grid::new(generations + 1, initial.len());
+        let mut row = initial.to_vec();
+        for generation in 0..=generations {
+            for (col, cell) in row.iter().enumerate() {
+                grid.set(*cell, generation, col);
+            }
+            if generation < generations {
+                row = self.next(&row);
+            }
+        }
+        grid
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Boundary, ElementaryAutomaton, Rule};
+    use crate::intro::conway::{Cell, Grid};
+    use rand::Rng;
+
+    fn row(line: &str) -> Vec<Cell> {
+        line.chars()
+            .map(|ch| if ch == 'O' { Cell::Alive } else { Cell::Dead })
+            .collect()
+    }
+
+    fn grid(lines: &[&str]) -> Grid<Cell> {
+        let cells: Vec<Cell> = lines.iter().flat_map(|line| row(line)).collect();
+        Grid::from_slice(&cells, lines.len(), lines[0].len())
+    }
+
+    #[test]
+    fn rule_30() {
+        let automaton = ElementaryAutomaton::new(30).with_boundary(Boundary::Fixed(Cell::Dead));
+        let diagram = automaton.diagram(&row("...O..."), 3);
+        assert!(diagram == grid(&["...O...", "..OOO..", ".OO..O.", "OO.OOOO"]));
+    }
+
+    #[test]
+    fn rule_90() {
+        let automaton = ElementaryAutomaton::new(90).with_boundary(Boundary::Fixed(Cell::Dead));
+        let diagram = automaton.diagram(&row("....O...."), 4);
+        assert!(
+            diagram
+                == grid(&[
+                    "....O....",
+                    "...O.O...",
+                    "..O...O..",
+                    ".O.O.O.O.",
+                    "O.......O",
+                ])
+        );
+    }
+
+    #[test]
+    fn boundaries() {
+        let shift = ElementaryAutomaton::new(170);
+        assert_eq!(shift.next(&row("O....")), row("....O"));
+        let diagram = shift.diagram(&row("OO..."), 5);
+        assert_eq!(diagram.size(), (5, 6));
+        for col in 0..5 {
+            assert!(diagram.get(0, col) == diagram.get(5, col));
+        }
+
+        let fixed = shift.with_boundary(Boundary::Fixed(Cell::Alive));
+        assert_eq!(fixed.next(&row("O....")), row("....O"));
+        assert_eq!(fixed.next(&row(".....")), row("....O"));
+        let fixed = shift.with_boundary(Boundary::Fixed(Cell::Dead));
+        assert_eq!(fixed.next(&row("O....")), row("....."));
+    }
+
+    #[test]
+    fn totalistic() {
+        let automaton = ElementaryAutomaton::totalistic(2, 0b10100);
+        assert_eq!(automaton.radius(), 2);
+        assert_eq!(
+            automaton.rule(),
+            Rule::Totalistic {
+                radius: 2,
+                code: 20
+            }
+        );
+        assert_eq!(automaton.next(&row("..O....OO.")), row("O.....OOOO"));
+
+        let xor = ElementaryAutomaton::new(150);
+        let odd = ElementaryAutomaton::totalistic(1, 0b1010);
+        let mut rng = rand::thread_rng();
+        for _ in 0..100 {
+            let line: Vec<Cell> = (0..20)
+                .map(|_| if rng.gen() { Cell::Alive } else { Cell::Dead })
+                .collect();
+            assert_eq!(xor.next(&line), odd.next(&line));
+            assert!(xor.diagram(&line, 10) == odd.diagram(&line, 10));
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn totalistic_code_too_wide() {
+        ElementaryAutomaton::totalistic(1, 0b10000);
+    }
+}
@@ -0,0 +1,346 @@
+#![forbid(unsafe_code)]
+
+use super::storage::Storage;
+use super::{flip, Cell, GameOfLife, Grid};
+use std::collections::{BTreeMap, VecDeque};
+use std::mem::{size_of, size_of_val};
+use thiserror::Error;
+
+////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct HistoryConfig {
+    // A full copy of the grid is kept every `keyframe_interval` generations,
+    // so `goto` never has to replay more than that many diffs.
+    pub keyframe_interval: usize,
+    // Approximate number of bytes the diffs and keyframes may take. The oldest
+    // generations are forgotten once it is exceeded.
+    pub memory_budget: usize,
+}
+
+impl Default for HistoryConfig {
+    fn default() -> Self {
+        HistoryConfig {
+            keyframe_interval: 32,
+            memory_budget: 16 << 20,
+        }
+    }
+}
+
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum HistoryError {
+    #[error("history is disabled")]
+    Disabled,
+    #[error("generations before {oldest} are not kept")]
+    Forgotten { oldest: usize },
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Clone)]
+pub(super) struct History<S> {
+    config: HistoryConfig,
+    oldest: usize,
+    // diffs[i] holds the cells that differ between generations `oldest + i`
+    // and `oldest + i + 1`. Flipping them moves a grid either way.
+    diffs: VecDeque<Vec<(usize, usize)>>,
+    keyframes: BTreeMap<usize, Grid<Cell, S>>,
+    memory: usize,
+}
+
+impl<S: Storage<Cell> + Clone> History<S> {
+    fn new(config: HistoryConfig, grid: &Grid<Cell, S>, generation: usize) -> Self {
+        let mut history = History {
+            config,
+            oldest: generation,
+            diffs: VecDeque::new(),
+            keyframes: BTreeMap::new(),
+            memory: 0,
+        };
+        history.add_keyframe(grid, generation);
+        history
+    }
+
+    fn newest(&self) -> usize {
+        self.oldest + self.diffs.len()
+    }
+
+    pub(super) fn record(
+        &mut self,
+        diff: Vec<(usize, usize)>,
+        next: &Grid<Cell, S>,
+        generation: usize,
+    ) {
+        self.memory += diff_size(&diff);
+        self.diffs.push_back(diff);
+        if generation.is_multiple_of(self.config.keyframe_interval.max(1)) {
+            self.add_keyframe(next, generation);
+        }
+        self.shrink();
+    }
+
+    // Drops every generation after `generation`.
+    pub(super) fn forget_after(&mut self, generation: usize) {
+        while self.newest() > generation.max(self.oldest) {
+            let diff = self.diffs.pop_back().unwrap();
+            self.memory -= diff_size(&diff);
+        }
+        while let Some(entry) = self.keyframes.last_entry() {
+            if *entry.key() <= generation {
+                break;
+            }
+            self.memory -= keyframe_size(entry.get());
+            entry.remove();
+        }
+    }
+
+    fn add_keyframe(&mut self, grid: &Grid<Cell, S>, generation: usize) {
+        self.memory += keyframe_size(grid);
+        self.keyframes.insert(generation, grid.clone());
+    }
+
+    fn shrink(&mut self) {
+        while self.memory > self.config.memory_budget {
+            let Some(diff) = self.diffs.pop_front() else {
+                break;
+            };
+            self.memory -= diff_size(&diff);
+            self.oldest += 1;
+            while let Some(entry) = self.keyframes.first_entry() {
+                if *entry.key() >= self.oldest {
+                    break;
+                }
+                self.memory -= keyframe_size(entry.get());
+                entry.remove();
+            }
+        }
+    }
+}
+
+impl<S: Storage<Cell>> PartialEq for History<S> {
+    fn eq(&self, other: &Self) -> bool {
+        self.config == other.config
+            && self.oldest == other.oldest
+            && self.diffs == other.diffs
+            && self.keyframes == other.keyframes
+            && self.memory == other.memory
+    }
+}
+
+impl<S: Storage<Cell>> Eq for History<S> {}
+
+fn diff_size(diff: &[(usize, usize)]) -> usize {
+    size_of::<Vec<(usize, usize)>>() + size_of_val(diff)
+}
+
+fn keyframe_size<S: Storage<Cell>>(grid: &Grid<Cell, S>) -> usize {
+    size_of::<Grid<Cell, S>>() + size_of::<Cell>() * grid.storage().cells().count()
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+impl<S: Storage<Cell> + Clone> GameOfLife<S> {
+    pub fn enable_history(&mut self, config: HistoryConfig) {
+        self.history = Some(History::new(config, &self.grid, self.generation));
+    }
+
+    pub fn disable_history(&mut self) {
+        self.history = None;
+    }
+
+    // The oldest and the newest generations `goto` can reach without
+    // recomputing anything.
+    pub fn history_range(&self) -> Option<(usize, usize)> {
+        self.history
+            .as_ref()
+            .map(|history| (history.oldest, history.newest()))
+    }
+
+    pub fn step_back(&mut self) -> Result<(), HistoryError> {
+        let history = self.history.as_ref().ok_or(HistoryError::Disabled)?;
+        if self.generation == history.oldest {
+            return Err(HistoryError::Forgotten {
+                oldest: history.oldest,
+            });
+        }
+        self.goto(self.generation - 1)
+    }
+
+    pub fn goto(&mut self, generation: usize) -> Result<(), HistoryError> {
+        let Some(history) = &self.history else {
+            if generation < self.generation {
+                return Err(HistoryError::Disabled);
+            }
+            while self.generation < generation {
+                self.step();
+            }
+            return Ok(());
+        };
+        if generation < history.oldest {
+            return Err(HistoryError::Forgotten {
+                oldest: history.oldest,
+            });
+        }
+
+        let target = generation.min(history.newest());
+        let mut start = self.generation;
+        let mut cost = start.abs_diff(target);
+        let before = history.keyframes.range(..=target).next_back();
+        let after = history.keyframes.range(target..).next();
+        for (&key, _) in before.into_iter().chain(after) {
+            if key.abs_diff(target) < cost {
+                start = key;
+                cost = key.abs_diff(target);
+            }
+        }
+        if start != self.generation {
+            self.grid = history.keyframes[&start].clone();
+        }
+        for current in target..start {
+            flip(&mut self.grid, &history.diffs[current - history.oldest]);
+        }
+        for current in start..target {
+            flip(&mut self.grid, &history.diffs[current - history.oldest]);
+        }
+        self.generation = target;
+        if let Some(active) = &mut self.active {
+            active.mark_all();
+        }
+
+        while self.generation < generation {
+            self.step();
+        }
+        Ok(())
+    }
+
+    // Moves forward along the already recorded diffs after stepping back.
+    pub(super) fn replay_step(&mut self) -> Option<Vec<(usize, usize)>> {
+        let history = self.history.as_ref()?;
+        if self.generation >= history.newest() {
+            return None;
+        }
+        let diff = history.diffs[self.generation - history.oldest].clone();
+        flip(&mut self.grid, &diff);
+        if let Some(active) = &mut self.active {
+            active.mark(&diff);
+        }
+        self.generation += 1;
+        Some(diff)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{HistoryConfig, HistoryError};
+    use crate::intro::conway::{Cell, GameOfLife, Grid};
+    use rand::Rng;
+
+    fn random_game(rows: usize, cols: usize) -> GameOfLife {
+        let mut rng = rand::thread_rng();
+        let cells: Vec<Cell> = (0..rows * cols)
+            .map(|_| {
+                if rng.gen_range(0..3) == 0 {
+                    Cell::Alive
+                } else {
+                    Cell::Dead
+                }
+            })
+            .collect();
+        GameOfLife::from_grid(Grid::from_slice(&cells, rows, cols))
+    }
+
+    fn generations(game: &mut GameOfLife, count: usize) -> Vec<Grid<Cell>> {
+        let mut res = vec![game.get_grid().clone()];
+        for _ in 0..count {
+            game.step();
+            res.push(game.get_grid().clone());
+        }
+        res
+    }
+
+    #[test]
+    fn step_back() {
+        let mut plain = random_game(16, 16);
+        let mut game = GameOfLife::from_grid(plain.get_grid().clone());
+        game.enable_history(HistoryConfig::default());
+        let expected = generations(&mut plain, 40);
+        assert!(generations(&mut game, 40) == expected);
+        assert_eq!(game.generation(), 40);
+        assert_eq!(game.history_range(), Some((0, 40)));
+
+        for generation in (0..40).rev() {
+            game.step_back().unwrap();
+            assert_eq!(game.generation(), generation);
+            assert!(game.get_grid() == &expected[generation]);
+        }
+        assert_eq!(game.step_back(), Err(HistoryError::Forgotten { oldest: 0 }));
+
+        for grid in &expected[1..] {
+            game.step();
+            assert!(game.get_grid() == grid);
+        }
+    }
+
+    #[test]
+    fn goto() {
+        let mut plain = random_game(20, 20);
+        let mut game = GameOfLife::from_grid(plain.get_grid().clone());
+        game.enable_history(HistoryConfig {
+            keyframe_interval: 8,
+            ..HistoryConfig::default()
+        });
+        let expected = generations(&mut plain, 100);
+        game.goto(60).unwrap();
+        assert!(game.get_grid() == &expected[60]);
+
+        let mut rng = rand::thread_rng();
+        for _ in 0..200 {
+            let generation = rng.gen_range(0..=100);
+            game.goto(generation).unwrap();
+            assert_eq!(game.generation(), generation);
+            assert!(game.get_grid() == &expected[generation]);
+        }
+        game.goto(100).unwrap();
+        assert!(game.get_grid() == &expected[100]);
+        assert_eq!(game.history_range(), Some((0, 100)));
+    }
+
+    #[test]
+    fn memory_budget() {
+        let mut game = random_game(10, 10);
+        game.enable_history(HistoryConfig {
+            keyframe_interval: 4,
+            memory_budget: 4096,
+        });
+        let expected = generations(&mut game, 200);
+        let (oldest, newest) = game.history_range().unwrap();
+        assert_eq!(newest, 200);
+        assert!(oldest > 0);
+        assert_eq!(
+            game.goto(oldest - 1),
+            Err(HistoryError::Forgotten { oldest })
+        );
+        game.goto(oldest).unwrap();
+        assert!(game.get_grid() == &expected[oldest]);
+        game.goto(200).unwrap();
+        assert!(game.get_grid() == &expected[200]);
+    }
+
+    #[test]
+    fn disabled() {
+        let mut game = random_game(5, 5);
+        assert_eq!(game.history_range(), None);
+        assert_eq!(game.step_back(), Err(HistoryError::Disabled));
+        game.goto(3).unwrap();
+        assert_eq!(game.generation(), 3);
+        assert_eq!(game.goto(2), Err(HistoryError::Disabled));
+
+        game.enable_history(HistoryConfig::default());
+        assert_eq!(game.history_range(), Some((3, 3)));
+        assert_eq!(game.step_back(), Err(HistoryError::Forgotten { oldest: 3 }));
+        game.step();
+        game.step_back().unwrap();
+        game.disable_history();
+        assert_eq!(game.step_back(), Err(HistoryError::Disabled));
+    }
+}
@@ -0,0 +1,404 @@
+#![forbid(unsafe_code)]
+
+use super::storage::{Sparse, Storage};
+use super::Grid;
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, VecDeque};
+
+////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Neighbourhood {
+    Four,
+    Eight,
+}
+
+impl Neighbourhood {
+    // The least number of moves between two cells on an empty grid: Manhattan
+    // distance for `Four` and Chebyshev distance for `Eight`. It never
+    // overestimates, so it is a valid A* heuristic when every move costs at
+    // least 1.
+    pub fn distance(self, from: (usize, usize), to: (usize, usize)) -> usize {
+        let (rows, cols) = (from.0.abs_diff(to.0), from.1.abs_diff(to.1));
+        match self {
+            Neighbourhood::Four => rows + cols,
+            Neighbourhood::Eight => rows.max(cols),
+        }
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+// Searches from a cell (`bfs`, `a_star`, `region`) keep their bookkeeping in
+// sparse grids, so they cost memory only for the cells they reach, whatever
+// the storage of the searched grid. Transforms of the whole grid
+// (`distance_transform`, `label_regions`) visit every cell anyway and return
+// dense grids of the same size.
+impl<T: Clone + Default, S: Storage<T>> Grid<T, S> {
+    pub fn neighbours_in(
+        &self,
+        row: usize,
+        col: usize,
+        neighbourhood: Neighbourhood,
+    ) -> impl Iterator<Item = (usize, usize)> + '_ {
+        self.neighbours(row, col)
+            .filter(move |&(r, c)| neighbourhood == Neighbourhood::Eight || r == row || c == col)
+    }
+
+    // Shortest path from `start` to `goal` (both included) through cells for
+    // which `passable` holds.
+    pub fn bfs<P: Fn(&T) -> bool>(
+        &self,
+        start: (usize, usize),
+        goal: (usize, usize),
+        neighbourhood: Neighbourhood,
+        passable: P,
+    ) -> Option<Vec<(usize, usize)>> {
+        if !passable(self.get(start.0, start.1)) || !passable(self.get(goal.0, goal.1)) {
+            return None;
+        }
+        let mut parents = Grid::<_, Sparse<_>>::with_storage(self.rows, self.cols);
+        parents.set(Some(start), start.0, start.1);
+        let mut queue = VecDeque::from([start]);
+        while let Some((row, col)) = queue.pop_front() {
+            if (row, col) == goal {
+                return Some(path(&parents, start, goal));
+            }
+            for (r, c) in self.neighbours_in(row, col, neighbourhood) {
+                if parents.get(r, c).is_none() && passable(self.get(r, c)) {
+                    parents.set(Some((row, col)), r, c);
+                    queue.push_back((r, c));
+                }
+            }
+        }
+        None
+    }
+
+    // Cheapest path where `cost(from, to)` is the price of moving between two
+    // neighbouring cells, or `None` if the move is not allowed.
+    pub fn dijkstra<C: Fn(&T, &T) -> Option<u64>>(
+        &self,
+        start: (usize, usize),
+        goal: (usize, usize),
+        neighbourhood: Neighbourhood,
+        cost: C,
+    ) -> Option<(u64, Vec<(usize, usize)>)> {
+        self.a_star(start, goal, neighbourhood, cost, |_| 0)
+    }
+
+    // Same as `dijkstra`, guided by a `heuristic` that must not overestimate
+    // the remaining cost to the goal.
+    pub fn a_star<C: Fn(&T, &T) -> Option<u64>, H: Fn((usize, usize)) -> u64>(
+        &self,
+        start: (usize, usize),
+        goal: (usize, usize),
+        neighbourhood: Neighbourhood,
+        cost: C,
+        heuristic: H,
+    ) -> Option<(u64, Vec<(usize, usize)>)> {
+        let mut best = Grid::<_, Sparse<_>>::with_storage(self.rows, self.cols);
+        let mut parents = Grid::<_, Sparse<_>>::with_storage(self.rows, self.cols);
+        let mut heap = BinaryHeap::new();
+        best.set(Some(0), start.0, start.1);
+        parents.set(Some(start), start.0, start.1);
+        heap.push(Reverse((heuristic(start), 0, start)));
+        while let Some(Reverse((_, spent, (row, col)))) = heap.pop() {
+            if (row, col) == goal {
+                return Some((spent, path(&parents, start, goal)));
+            }
+            if best.get(row, col).is_some_and(|best| best < spent) {
+                continue;
+            }
+            for (r, c) in self.neighbours_in(row, col, neighbourhood) {
+                let Some(step) = cost(self.get(row, col), self.get(r, c)) else {
+                    continue;
+                };
+                let total = spent + step;
+                if best.get(r, c).is_none_or(|best| total < best) {
+                    best.set(Some(total), r, c);
+                    parents.set(Some((row, col)), r, c);
+                    heap.push(Reverse((total + heuristic((r, c)), total, (r, c))));
+                }
+            }
+        }
+        None
+    }
+
+    pub fn distance_transform<F: Fn(&T) -> bool>(
+        &self,
+        neighbourhood: Neighbourhood,
+        is_source: F,
+    ) -> Grid<Option<usize>> {
+        let mut distances = Grid::<Option<usize>>::new(self.rows, self.cols);
+        let mut queue = VecDeque::new();
+        for row in 0..self.rows {
+            for col in 0..self.cols {
+                if is_source(self.get(row, col)) {
+                    distances.set(Some(0), row, col);
+                    queue.push_back((row, col));
+                }
+            }
+        }
+        while let Some((row, col)) = queue.pop_front() {
+            let distance = distances.get(row, col).unwrap();
+            for (r, c) in self.neighbours_in(row, col, neighbourhood) {
+                if distances.get(r, c).is_none() {
+                    distances.set(Some(distance + 1), r, c);
+                    queue.push_back((r, c));
+                }
+            }
+        }
+        distances
+    }
+}
+
+impl<T: Clone + Default + PartialEq, S: Storage<T>> Grid<T, S> {
+    // Cells connected to `start` through cells equal to it.
+    pub fn region(
+        &self,
+        start: (usize, usize),
+        neighbourhood: Neighbourhood,
+    ) -> Vec<(usize, usize)> {
+        let value = self.get(start.0, start.1);
+        let mut visited = Grid::<bool, Sparse<bool>>::with_storage(self.rows, self.cols);
+        visited.set(true, start.0, start.1);
+        let mut res = vec![];
+        let mut queue = VecDeque::from([start]);
+        while let Some((row, col)) = queue.pop_front() {
+            res.push((row, col));
+            for (r, c) in self.neighbours_in(row, col, neighbourhood) {
+                if !*visited.get(r, c) && self.get(r, c) == value {
+                    visited.set(true, r, c);
+                    queue.push_back((r, c));
+                }
+            }
+        }
+        res
+    }
+
+    // Paints the region of `start` with `value`, returning its size.
+    pub fn flood_fill(
+        &mut self,
+        start: (usize, usize),
+        value: T,
+        neighbourhood: Neighbourhood,
+    ) -> usize {
+        let region = self.region(start, neighbourhood);
+        for &(row, col) in &region {
+            self.set(value.clone(), row, col);
+        }
+        region.len()
+    }
+
+    // Numbers the regions of equal cells from 0 in row-major order of their
+    // first cell, returning the labels and the number of regions.
+    pub fn label_regions(&self, neighbourhood: Neighbourhood) -> (Grid<usize>, usize) {
+        let mut labels = Grid::<Option<usize>>::new(self.rows, self.cols);
+        let mut count = 0;
+        for row in 0..self.rows {
+            for col in 0..self.cols {
+                if labels.get(row, col).is_some() {
+                    continue;
+                }
+                labels.set(Some(count), row, col);
+                let mut queue = VecDeque::from([(row, col)]);
+                while let Some((row, col)) = queue.pop_front() {
+                    for (r, c) in self.neighbours_in(row, col, neighbourhood) {
+                        if labels.get(r, c).is_none() && self.get(r, c) == self.get(row, col) {
+                            labels.set(Some(count), r, c);
+                            queue.push_back((r, c));
+                        }
+                    }
+                }
+                count += 1;
+            }
+        }
+        let mut res = Grid::new(self.rows, self.cols);
+        for ((row, col), label) in labels.storage().cells() {
+            res.set(label.unwrap(), row, col);
+        }
+        (res, count)
+    }
+}
+
+type Parents = Grid<Option<(usize, usize)>, Sparse<Option<(usize, usize)>>>;
+
+fn path(parents: &Parents, start: (usize, usize), goal: (usize, usize)) -> Vec<(usize, usize)> {
+    let mut res = vec![goal];
+    let mut current = goal;
+    while current != start {
+        current = parents.get(current.0, current.1).unwrap();
+        res.push(current);
+    }
+    res.reverse();
+    res
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Neighbourhood;
+    use crate::intro::conway::Grid;
+    use rand::Rng;
+
+    fn map(lines: &[&str]) -> Grid<char> {
+        let cells: Vec<char> = lines.iter().flat_map(|line| line.chars()).collect();
+        Grid::from_slice(&cells, lines.len(), lines[0].len())
+    }
+
+    fn random_map(rows: usize, cols: usize) -> Grid<u8> {
+        let mut rng = rand::thread_rng();
+        let cells: Vec<u8> = (0..rows * cols).map(|_| rng.gen_range(0..4)).collect();
+        Grid::from_slice(&cells, rows, cols)
+    }
+
+    fn adjacent(path: &[(usize, usize)], neighbourhood: Neighbourhood) -> bool {
+        path.windows(2)
+            .all(|pair| neighbourhood.distance(pair[0], pair[1]) == 1)
+    }
+
+    #[test]
+    fn neighbours_in() {
+        let grid = Grid::<u8>::new(3, 3);
+        assert_eq!(
+            grid.neighbours_in(1, 1, Neighbourhood::Four)
+                .collect::<Vec<_>>(),
+            vec![(0, 1), (1, 0), (1, 2), (2, 1)]
+        );
+        assert_eq!(grid.neighbours_in(1, 1, Neighbourhood::Eight).count(), 8);
+        assert_eq!(grid.neighbours_in(0, 0, Neighbourhood::Four).count(), 2);
+    }
+
+    #[test]
+    fn bfs() {
+        let maze = map(&[
+            "..#....", //
+            "..#.##.", //
+            "..#..#.", //
+            "....#..", //
+        ]);
+        let open = |c: &char| *c == '.';
+        let path = maze.bfs((0, 0), (0, 3), Neighbourhood::Four, open).unwrap();
+        assert_eq!(path.len(), 10);
+        assert_eq!(path[0], (0, 0));
+        assert_eq!(path[9], (0, 3));
+        assert!(adjacent(&path, Neighbourhood::Four));
+        assert!(path.iter().all(|&(r, c)| open(maze.get(r, c))));
+
+        let path = maze
+            .bfs((0, 0), (0, 3), Neighbourhood::Eight, open)
+            .unwrap();
+        assert_eq!(path.len(), 7);
+        assert!(adjacent(&path, Neighbourhood::Eight));
+
+        assert_eq!(
+            maze.bfs((0, 0), (0, 6), Neighbourhood::Four, open)
+                .unwrap()
+                .len(),
+            13
+        );
+        assert_eq!(maze.bfs((0, 0), (0, 2), Neighbourhood::Four, open), None);
+        assert_eq!(
+            maze.bfs((2, 3), (2, 3), Neighbourhood::Four, open),
+            Some(vec![(2, 3)])
+        );
+
+        let walled = map(&[".#.", "##.", "..."]);
+        assert_eq!(walled.bfs((0, 0), (2, 2), Neighbourhood::Eight, open), None);
+    }
+
+    #[test]
+    fn dijkstra() {
+        let costs = Grid::from_slice(&[1u64, 9, 1, 1, 9, 1, 1, 1, 1], 3, 3);
+        let cost = |_: &u64, to: &u64| Some(*to);
+        let (total, path) = costs
+            .dijkstra((0, 0), (0, 2), Neighbourhood::Four, cost)
+            .unwrap();
+        assert_eq!(total, 6);
+        assert_eq!(
+            path,
+            vec![(0, 0), (1, 0), (2, 0), (2, 1), (2, 2), (1, 2), (0, 2)]
+        );
+
+        let (total, _) = costs
+            .dijkstra((0, 0), (0, 2), Neighbourhood::Eight, cost)
+            .unwrap();
+        assert_eq!(total, 4);
+
+        let blocked = |_: &u64, to: &u64| if *to == 9 { None } else { Some(1) };
+        let (total, _) = costs
+            .dijkstra((0, 0), (0, 2), Neighbourhood::Four, blocked)
+            .unwrap();
+        assert_eq!(total, 6);
+        let walls = Grid::from_slice(&[1u64, 9, 1, 1, 9, 1, 1, 9, 1], 3, 3);
+        assert_eq!(
+            walls.dijkstra((0, 0), (0, 2), Neighbourhood::Eight, blocked),
+            None
+        );
+    }
+
+    #[test]
+    fn a_star_agrees_with_dijkstra() {
+        for neighbourhood in [Neighbourhood::Four, Neighbourhood::Eight] {
+            for _ in 0..50 {
+                let grid = random_map(12, 17);
+                let cost = |_: &u8, to: &u8| if *to == 0 { None } else { Some(*to as u64) };
+                let goal = (11, 16);
+                let expected = grid.dijkstra((0, 0), goal, neighbourhood, cost);
+                let found = grid.a_star((0, 0), goal, neighbourhood, cost, |cell| {
+                    neighbourhood.distance(cell, goal) as u64
+                });
+                assert_eq!(expected.as_ref().map(|r| r.0), found.as_ref().map(|r| r.0));
+                if let Some((total, path)) = found {
+                    assert!(adjacent(&path, neighbourhood));
+                    let sum: u64 = path[1..].iter().map(|&(r, c)| *grid.get(r, c) as u64).sum();
+                    assert_eq!(sum, total);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn regions() {
+        let mut grid = map(&[
+            "aab", //
+            "bab", //
+            "bba", //
+        ]);
+        let mut region = grid.region((0, 0), Neighbourhood::Four);
+        region.sort();
+        assert_eq!(region, vec![(0, 0), (0, 1), (1, 1)]);
+        assert_eq!(grid.region((0, 0), Neighbourhood::Eight).len(), 4);
+
+        let (labels, count) = grid.label_regions(Neighbourhood::Four);
+        assert_eq!(count, 4);
+        assert_eq!(*labels.get(0, 1), 0);
+        assert_eq!(*labels.get(1, 2), 1);
+        assert_eq!(*labels.get(2, 1), 2);
+        assert_eq!(*labels.get(2, 2), 3);
+        assert_eq!(grid.label_regions(Neighbourhood::Eight).1, 2);
+
+        assert_eq!(grid.flood_fill((1, 0), 'c', Neighbourhood::Four), 3);
+        assert!(grid == map(&["aab", "cab", "cca"]));
+    }
+
+    #[test]
+    fn distance_transform() {
+        for neighbourhood in [Neighbourhood::Four, Neighbourhood::Eight] {
+            let grid = random_map(9, 13);
+            let distances = grid.distance_transform(neighbourhood, |c| *c == 0);
+            let sources: Vec<(usize, usize)> = (0..9)
+                .flat_map(|r| (0..13).map(move |c| (r, c)))
+                .filter(|&(r, c)| *grid.get(r, c) == 0)
+                .collect();
+            for row in 0..9 {
+                for col in 0..13 {
+                    let expected = sources
+                        .iter()
+                        .map(|&source| neighbourhood.distance(source, (row, col)))
+                        .min();
+                    assert_eq!(*distances.get(row, col), expected);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,224 @@
+#![forbid(unsafe_code)]
+
+use super::rule::Rule;
+use super::{Cell, Grid};
+
+////////////////////////////////////////////////////////////////////////////////
+
+// Backtracking search for grids that `GameOfLife::step` under `rule` turns
+// into the target. Cells of the predecessor are assigned in row-major order,
+// and after every assignment each target cell around it is checked for whether
+// some completion of its neighbourhood can still produce it.
+pub fn find_predecessor(target: &Grid<Cell>, rule: &Rule) -> Option<Grid<Cell>> {
+    let mut res = None;
+    search(target, rule, &mut |grid| {
+        res = Some(grid);
+        false
+    });
+    res
+}
+
+pub fn find_all_predecessors(target: &Grid<Cell>, rule: &Rule) -> Vec<Grid<Cell>> {
+    let mut res = vec![];
+    search(target, rule, &mut |grid| {
+        res.push(grid);
+        true
+    });
+    res
+}
+
+pub fn is_garden_of_eden(target: &Grid<Cell>, rule: &Rule) -> bool {
+    find_predecessor(target, rule).is_none()
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum State {
+    Unknown,
+    Known(Cell),
+}
+
+struct Search<'a, F> {
+    target: &'a Grid<Cell>,
+    rule: &'a Rule,
+    cells: Vec<State>,
+    found: F,
+}
+
+fn search<F: FnMut(Grid<Cell>) -> bool>(target: &Grid<Cell>, rule: &Rule, found: &mut F) {
+    let mut search = Search {
+        target,
+        rule,
+        cells: vec![State::Unknown; target.rows * target.cols],
+        found,
+    };
+    if search.consistent_everywhere() {
+        search.assign(0);
+    }
+}
+
+impl<'a, F: FnMut(Grid<Cell>) -> bool> Search<'a, F> {
+    // Returns false once the callback asked to stop.
+    fn assign(&mut self, index: usize) -> bool {
+        if index == self.cells.len() {
+            let cells: Vec<Cell> = self
+                .cells
+                .iter()
+                .map(|state| match state {
+                    State::Known(cell) => *cell,
+                    State::Unknown => unreachable!(),
+                })
+                .collect();
+            let grid = Grid::from_slice(&cells, self.target.rows, self.target.cols);
+            return (self.found)(grid);
+        }
+        let (row, col) = (index / self.target.cols, index % self.target.cols);
+        for cell in [Cell::Dead, Cell::Alive] {
+            self.cells[index] = State::Known(cell);
+            if self.consistent_around(row, col) && !self.assign(index + 1) {
+                return false;
+            }
+        }
+        self.cells[index] = State::Unknown;
+        true
+    }
+
+    fn consistent_everywhere(&self) -> bool {
+        (0..self.target.rows).all(|row| (0..self.target.cols).all(|col| self.consistent(row, col)))
+    }
+
+    fn consistent_around(&self, row: usize, col: usize) -> bool {
+        self.consistent(row, col)
+            && self
+                .target
+                .neighbours(row, col)
+                .all(|(row, col)| self.consistent(row, col))
+    }
+
+    // Whether some completion of the unknown cells in the 3x3 square around
+    // `(row, col)` turns it into the target cell.
+    fn consistent(&self, row: usize, col: usize) -> bool {
+        let (mut known, mut unknown) = (0, vec![]);
+        for (i, r) in (row as isize - 1..=row as isize + 1).enumerate() {
+            for (j, c) in (col as isize - 1..=col as isize + 1).enumerate() {
+                if r < 0
+                    || c < 0
+                    || r >= self.target.rows as isize
+                    || c >= self.target.cols as isize
+                {
+                    continue;
+                }
+                match self.cells[c as usize + r as usize * self.target.cols] {
+                    State::Known(Cell::Alive) => known |= 1 << (3 * i + j),
+                    State::Known(Cell::Dead) => {}
+                    State::Unknown => unknown.push(3 * i + j),
+                }
+            }
+        }
+        let goal = *self.target.get(row, col);
+        (0..1usize << unknown.len()).any(|completion| {
+            let index = unknown
+                .iter()
+                .enumerate()
+                .filter(|&(k, _)| completion >> k & 1 == 1)
+                .fold(known, |index, (_, bit)| index | 1 << bit);
+            self.rule.next(index) == goal
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{find_all_predecessors, find_predecessor, is_garden_of_eden};
+    use crate::intro::conway::rule::Rule;
+    use crate::intro::conway::{Cell, GameOfLife, Grid};
+
+    fn grid_from_bits(bits: usize, rows: usize, cols: usize) -> Grid<Cell> {
+        let cells: Vec<Cell> = (0..rows * cols)
+            .map(|i| {
+                if bits >> i & 1 == 1 {
+                    Cell::Alive
+                } else {
+                    Cell::Dead
+                }
+            })
+            .collect();
+        Grid::from_slice(&cells, rows, cols)
+    }
+
+    fn next(grid: &Grid<Cell>, rule: &Rule) -> Grid<Cell> {
+        let mut game = GameOfLife::from_grid(grid.clone());
+        game.set_rule(rule.clone());
+        game.step();
+        game.get_grid().clone()
+    }
+
+    #[test]
+    fn lonely_cell() {
+        let life = Rule::life();
+        let target = Grid::from_slice(&[Cell::Alive], 1, 1);
+        assert!(is_garden_of_eden(&target, &life));
+        assert!(find_all_predecessors(&target, &life).is_empty());
+
+        let target = Grid::from_slice(&[Cell::Dead], 1, 1);
+        assert_eq!(find_all_predecessors(&target, &life).len(), 2);
+
+        let target = Grid::from_slice(&[Cell::Alive], 1, 1);
+        let rule = "B0/S".parse().unwrap();
+        assert_eq!(find_all_predecessors(&target, &rule).len(), 1);
+    }
+
+    #[test]
+    fn found_predecessor_steps_into_target() {
+        #[rustfmt::skip]
+        let cells = [
+            0, 0, 0, 0, 0, 0,
+            0, 0, 1, 0, 0, 0,
+            0, 0, 0, 1, 0, 0,
+            0, 1, 1, 1, 0, 0,
+            0, 0, 0, 0, 0, 0,
+            0, 0, 0, 0, 0, 0,
+        ];
+        let cells: Vec<Cell> = cells
+            .iter()
+            .map(|&c| if c == 1 { Cell::Alive } else { Cell::Dead })
+            .collect();
+        let target = Grid::from_slice(&cells, 6, 6);
+        let life = Rule::life();
+        let predecessor = find_predecessor(&target, &life).unwrap();
+        assert!(next(&predecessor, &life) == target);
+    }
+
+    fn compare_with_brute_force(rule: &Rule) {
+        let (rows, cols) = (3, 3);
+        let mut images = vec![0; 1 << (rows * cols)];
+        for bits in 0..1 << (rows * cols) {
+            let image = next(&grid_from_bits(bits, rows, cols), rule);
+            let index = (0..rows * cols)
+                .filter(|&i| *image.get(i / cols, i % cols) == Cell::Alive)
+                .map(|i| 1 << i)
+                .sum::<usize>();
+            images[index] += 1;
+        }
+        for (bits, &count) in images.iter().enumerate() {
+            let target = grid_from_bits(bits, rows, cols);
+            let all = find_all_predecessors(&target, rule);
+            assert_eq!(all.len(), count);
+            assert!(all.iter().all(|grid| next(grid, rule) == target));
+            assert_eq!(is_garden_of_eden(&target, rule), count == 0);
+        }
+    }
+
+    #[test]
+    fn compare_with_brute_force_life() {
+        compare_with_brute_force(&Rule::life());
+    }
+
+    #[test]
+    fn compare_with_brute_force_other_rules() {
+        for rule in ["B36/S23", "B2-a/S12", "B3/S4t"] {
+            compare_with_brute_force(&rule.parse().unwrap());
+        }
+    }
+}
@@ -0,0 +1,436 @@
+#![forbid(unsafe_code)]
+
+use super::storage::Storage;
+use super::{Cell, GameOfLife};
+use std::str::FromStr;
+use thiserror::Error;
+
+////////////////////////////////////////////////////////////////////////////////
+
+// Neighbours in Hensel order: N, NE, E, SE, S, SW, W, NW. Entries are bits of
+// the neighbourhood index, where the cell at `(row, col)` of the 3x3 square
+// around the center has bit `3 * row + col`.
+const RING: [usize; 8] = [1, 2, 5, 8, 7, 6, 3, 0];
+const CENTER: usize = 4;
+
+// One representative of every configuration, as positions in `RING`. The
+// configurations of 5..=7 neighbours are complements of those of 8 - n.
+const LETTERS: [&[(char, &[usize])]; 5] = [
+    &[],
+    &[('c', &[1]), ('e', &[0])],
+    &[
+        ('a', &[0, 1]),
+        ('c', &[1, 3]),
+        ('e', &[0, 2]),
+        ('i', &[0, 4]),
+        ('k', &[0, 3]),
+        ('n', &[1, 5]),
+    ],
+    &[
+        ('a', &[0, 1, 2]),
+        ('c', &[1, 3, 5]),
+        ('e', &[0, 2, 4]),
+        ('i', &[0, 1, 7]),
+        ('j', &[0, 1, 6]),
+        ('k', &[0, 2, 5]),
+        ('n', &[0, 1, 3]),
+        ('q', &[0, 1, 5]),
+        ('r', &[0, 1, 4]),
+        ('y', &[0, 3, 5]),
+    ],
+    &[
+        ('a', &[0, 1, 2, 3]),
+        ('c', &[1, 3, 5, 7]),
+        ('e', &[0, 2, 4, 6]),
+        ('i', &[0, 1, 3, 4]),
+        ('j', &[0, 1, 4, 6]),
+        ('k', &[0, 1, 3, 6]),
+        ('n', &[0, 1, 3, 7]),
+        ('q', &[0, 1, 2, 5]),
+        ('r', &[0, 1, 2, 4]),
+        ('t', &[0, 1, 4, 7]),
+        ('w', &[0, 1, 5, 6]),
+        ('y', &[0, 1, 3, 5]),
+        ('z', &[0, 1, 4, 5]),
+    ],
+];
+
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum RuleError {
+    #[error("expected a rule of the form \"B.../S...\"")]
+    Format,
+    #[error("unexpected character {0:?}")]
+    Unexpected(char),
+    #[error("there is no configuration {count}{letter}")]
+    UnknownLetter { count: usize, letter: char },
+}
+
+// An isotropic two-state rule on the Moore neighbourhood. The next state of
+// a cell is looked up by the index of its 3x3 neighbourhood.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Rule {
+    table: [bool; 512],
+}
+
+impl Rule {
+    // B3/S23.
+    pub fn life() -> Self {
+        let mut table = [false; 512];
+        for (index, alive) in table.iter_mut().enumerate() {
+            let count = (index & !(1 << CENTER)).count_ones();
+            *alive = count == 3 || count == 2 && index & 1 << CENTER != 0;
+        }
+        Rule { table }
+    }
+
+    pub(super) fn next(&self, index: usize) -> Cell {
+        if self.table[index] {
+            Cell::Alive
+        } else {
+            Cell::Dead
+        }
+    }
+
+    // Cells far from anything alive are born, so the whole board has to be
+    // swept every generation.
+    pub(super) fn births_on_empty(&self) -> bool {
+        self.table[0]
+    }
+}
+
+impl Default for Rule {
+    fn default() -> Self {
+        Self::life()
+    }
+}
+
+impl FromStr for Rule {
+    type Err = RuleError;
+
+    fn from_str(rule: &str) -> Result<Self, Self::Err> {
+        let mut birth = None;
+        let mut survival = None;
+        for part in rule.trim().split('/') {
+            let mut chars = part.chars();
+            let slot = match chars.next() {
+                Some('B' | 'b') => &mut birth,
+                Some('S' | 's') => &mut survival,
+                _ => return Err(RuleError::Format),
+            };
+            if slot.is_some() {
+                return Err(RuleError::Format);
+            }
+            *slot = Some(parse_conditions(chars.as_str())?);
+        }
+        let (Some(birth), Some(survival)) = (birth, survival) else {
+            return Err(RuleError::Format);
+        };
+        let mut table = [false; 512];
+        for (index, alive) in table.iter_mut().enumerate() {
+            let ring = ring_mask(index);
+            *alive = if index & 1 << CENTER != 0 {
+                survival[ring]
+            } else {
+                birth[ring]
+            };
+        }
+        Ok(Rule { table })
+    }
+}
+
+// Parses something like "2-a3ij4" into the set of neighbour configurations,
+// indexed by masks over `RING`.
+fn parse_conditions(conditions: &str) -> Result<[bool; 256], RuleError> {
+    let mut res = [false; 256];
+    let mut chars = conditions.chars().peekable();
+    while let Some(ch) = chars.next() {
+        let count = match ch.to_digit(10) {
+            Some(count @ 0..=8) => count as usize,
+            _ => return Err(RuleError::Unexpected(ch)),
+        };
+        let negated = chars.next_if_eq(&'-').is_some();
+        let mut letters = vec![];
+        while let Some(letter) = chars.next_if(|ch| ch.is_ascii_lowercase()) {
+            letters.push(letter);
+        }
+        if negated && letters.is_empty() {
+            return Err(RuleError::Format);
+        }
+        let mut selected = [letters.is_empty() || negated; 256];
+        for &letter in &letters {
+            let Some(configurations) = configurations(count, letter) else {
+                return Err(RuleError::UnknownLetter { count, letter });
+            };
+            for mask in configurations {
+                selected[mask] = !negated;
+            }
+        }
+        for mask in 0..256usize {
+            if mask.count_ones() as usize == count && selected[mask] {
+                res[mask] = true;
+            }
+        }
+    }
+    Ok(res)
+}
+
+// All rotations and reflections of the configuration `count` + `letter`.
+fn configurations(count: usize, letter: char) -> Option<Vec<usize>> {
+    let (letters, complement) = if count <= 4 {
+        (LETTERS[count], false)
+    } else {
+        (LETTERS[8 - count], true)
+    };
+    let &(_, positions) = letters.iter().find(|entry| entry.0 == letter)?;
+    let mut res = vec![];
+    for rotation in 0..4 {
+        for reflection in [false, true] {
+            let mut mask = 0;
+            for &position in positions {
+                let position = if reflection { 8 - position } else { position };
+                mask |= 1 << ((position + 2 * rotation) % 8);
+            }
+            if complement {
+                mask ^= 0xff;
+            }
+            if !res.contains(&mask) {
+                res.push(mask);
+            }
+        }
+    }
+    Some(res)
+}
+
+fn ring_mask(index: usize) -> usize {
+    RING.iter()
+        .enumerate()
+        .filter(|&(_, &bit)| index & 1 << bit != 0)
+        .fold(0, |mask, (position, _)| mask | 1 << position)
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+impl<S: Storage<Cell> + Clone> GameOfLife<S> {
+    pub fn rule(&self) -> &Rule {
+        &self.rule
+    }
+
+    // Generations after the current one may evolve differently now, so they
+    // are dropped from the history.
+    pub fn set_rule(&mut self, rule: Rule) {
+        self.rule = rule;
+        if let Some(history) = &mut self.history {
+            history.forget_after(self.generation);
+        }
+        if let Some(active) = &mut self.active {
+            active.mark_all();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{configurations, Rule, RuleError, LETTERS, RING};
+    use crate::intro::conway::active::ActiveConfig;
+    use crate::intro::conway::history::HistoryConfig;
+    use crate::intro::conway::{Cell, GameOfLife, Grid};
+    use rand::Rng;
+
+    fn random_grid(rows: usize, cols: usize) -> Grid<Cell> {
+        let mut rng = rand::thread_rng();
+        let cells: Vec<Cell> = (0..rows * cols)
+            .map(|_| if rng.gen() { Cell::Alive } else { Cell::Dead })
+            .collect();
+        Grid::from_slice(&cells, rows, cols)
+    }
+
+    // Neighbourhood index of a dead cell with the given neighbours alive.
+    fn index(neighbours: &[usize]) -> usize {
+        neighbours.iter().fold(0, |index, &n| index | 1 << RING[n])
+    }
+
+    #[test]
+    fn letters_cover_every_configuration() {
+        for count in 0..=8 {
+            let mut seen = [false; 256];
+            let letters = LETTERS[count.min(8 - count)];
+            for &(letter, _) in letters {
+                for mask in configurations(count, letter).unwrap() {
+                    assert_eq!(mask.count_ones() as usize, count);
+                    assert!(!seen[mask], "{}{} overlaps", count, letter);
+                    seen[mask] = true;
+                }
+            }
+            let total = (0..256usize)
+                .filter(|mask| mask.count_ones() as usize == count)
+                .count();
+            if count != 0 && count != 8 {
+                assert_eq!(seen.iter().filter(|&&s| s).count(), total);
+            }
+        }
+        assert_eq!(configurations(4, 'c').unwrap(), vec![0b1010_1010]);
+        assert_eq!(configurations(4, 'e').unwrap(), vec![0b0101_0101]);
+        assert_eq!(configurations(5, 'c').unwrap().len(), 4);
+        assert_eq!(configurations(1, 'e').unwrap().len(), 4);
+        assert!(configurations(4, 'b').is_none());
+    }
+
+    // Hensel's reference pictures, with the center left out.
+    const REFERENCE: [(&str, [&str; 3]); 29] = [
+        ("2a", [".OO", "...", "..."]),
+        ("2c", ["..O", "...", "..O"]),
+        ("2e", [".O.", "..O", "..."]),
+        ("2i", [".O.", "...", ".O."]),
+        ("2k", [".O.", "...", "..O"]),
+        ("2n", ["..O", "...", "O.."]),
+        ("3a", [".OO", "..O", "..."]),
+        ("3c", ["..O", "...", "O.O"]),
+        ("3e", [".O.", "..O", ".O."]),
+        ("3i", ["OOO", "...", "..."]),
+        ("3j", [".OO", "O..", "..."]),
+        ("3k", [".O.", "..O", "O.."]),
+        ("3n", [".OO", "...", "..O"]),
+        ("3q", [".OO", "...", "O.."]),
+        ("3r", [".OO", "...", ".O."]),
+        ("3y", [".O.", "...", "O.O"]),
+        ("4a", [".OO", "..O", "..O"]),
+        ("4c", ["O.O", "...", "O.O"]),
+        ("4e", [".O.", "O.O", ".O."]),
+        ("4i", ["...", "O.O", "O.O"]),
+        ("4j", [".OO", "O..", ".O."]),
+        ("4k", [".OO", "O..", "..O"]),
+        ("4n", ["OOO", "...", "..O"]),
+        ("4q", [".OO", "..O", "O.."]),
+        ("4r", [".OO", "..O", ".O."]),
+        ("4t", ["OOO", "...", ".O."]),
+        ("4w", [".OO", "O..", "O.."]),
+        ("4y", [".OO", "...", "O.O"]),
+        ("4z", [".OO", "...", "OO."]),
+    ];
+
+    #[test]
+    fn letters_match_reference() {
+        for (name, picture) in REFERENCE {
+            let index = picture
+                .iter()
+                .flat_map(|row| row.chars())
+                .enumerate()
+                .filter(|&(_, ch)| ch == 'O')
+                .fold(0, |index, (bit, _)| index | 1 << bit);
+            for (other, _) in REFERENCE {
+                let rule: Rule = format!("B{}/S", other).parse().unwrap();
+                let expected = if other == name {
+                    Cell::Alive
+                } else {
+                    Cell::Dead
+                };
+                assert_eq!(rule.next(index), expected, "{} as {}", name, other);
+            }
+        }
+    }
+
+    #[test]
+    fn parse() {
+        assert_eq!("B3/S23".parse::<Rule>(), Ok(Rule::life()));
+        assert_eq!("s23/b3".parse::<Rule>(), Ok(Rule::default()));
+        assert_eq!("B3/S2-ack3".parse::<Rule>(), "B3/S2ein3".parse());
+        assert_eq!("B3-/S23".parse::<Rule>(), Err(RuleError::Format));
+        assert_eq!("B3".parse::<Rule>(), Err(RuleError::Format));
+        assert_eq!("B3/S23/B3".parse::<Rule>(), Err(RuleError::Format));
+        assert_eq!("B39/S23".parse::<Rule>(), Err(RuleError::Unexpected('9')));
+        assert_eq!(
+            "B3/S2x".parse::<Rule>(),
+            Err(RuleError::UnknownLetter {
+                count: 2,
+                letter: 'x'
+            })
+        );
+        assert_eq!(
+            "B0a/S".parse::<Rule>(),
+            Err(RuleError::UnknownLetter {
+                count: 0,
+                letter: 'a'
+            })
+        );
+    }
+
+    #[test]
+    fn non_totalistic() {
+        let rule: Rule = "B2-a/S12".parse().unwrap();
+        assert_eq!(rule.next(index(&[0, 1])), Cell::Dead);
+        assert_eq!(rule.next(index(&[0, 4])), Cell::Alive);
+        assert_eq!(rule.next(index(&[3, 5])), Cell::Alive);
+        assert_eq!(rule.next(index(&[6, 7])), Cell::Dead);
+        assert_eq!(rule.next(index(&[0, 1, 2])), Cell::Dead);
+        assert_eq!(rule.next(index(&[0, 1]) | 1 << 4), Cell::Alive);
+        assert_eq!(rule.next(1 << 4), Cell::Dead);
+
+        let rule: Rule = "B3/S4t".parse().unwrap();
+        assert_eq!(rule.next(index(&[7, 0, 1, 4]) | 1 << 4), Cell::Alive);
+        assert_eq!(rule.next(index(&[0, 1, 2, 7]) | 1 << 4), Cell::Dead);
+    }
+
+    #[test]
+    fn steps_with_rule() {
+        let mut game = GameOfLife::from_grid(Grid::new(5, 5));
+        let mut grid = Grid::new(5, 5);
+        grid.set(Cell::Alive, 2, 2);
+        game.set_rule("B1/S".parse().unwrap());
+        let mut single = GameOfLife::from_grid(grid);
+        single.set_rule(game.rule().clone());
+        single.step();
+        let alive = (0..5)
+            .flat_map(|row| (0..5).map(move |col| (row, col)))
+            .filter(|&(row, col)| *single.get_grid().get(row, col) == Cell::Alive)
+            .count();
+        assert_eq!(alive, 8);
+
+        let grid = random_grid(20, 20);
+        let mut life = GameOfLife::from_grid(grid.clone());
+        let mut parsed = GameOfLife::from_grid(grid);
+        parsed.set_rule("B3/S23".parse().unwrap());
+        for _ in 0..20 {
+            life.step();
+            parsed.step();
+            assert!(life.get_grid() == parsed.get_grid());
+        }
+    }
+
+    #[test]
+    fn births_on_empty_with_active_region() {
+        let rule: Rule = "B0/S8".parse().unwrap();
+        let mut grid = Grid::new(16, 16);
+        grid.set(Cell::Alive, 0, 0);
+        let mut full = GameOfLife::from_grid(grid.clone());
+        full.set_rule(rule.clone());
+        let mut active = GameOfLife::from_grid(grid);
+        active.enable_active_region(ActiveConfig {
+            tile_size: 4,
+            full_sweep_percent: 100,
+        });
+        active.set_rule(rule);
+        for _ in 0..6 {
+            full.step();
+            active.step();
+            assert!(full.get_grid() == active.get_grid());
+        }
+    }
+
+    #[test]
+    fn changing_rule_drops_future_history() {
+        let mut game = GameOfLife::from_grid(random_grid(12, 12));
+        game.enable_history(HistoryConfig::default());
+        for _ in 0..10 {
+            game.step();
+        }
+        game.goto(4).unwrap();
+        game.set_rule("B36/S23".parse().unwrap());
+        assert_eq!(game.history_range(), Some((0, 4)));
+
+        let mut expected = GameOfLife::from_grid(game.get_grid().clone());
+        expected.set_rule(game.rule().clone());
+        game.step();
+        expected.step();
+        assert!(game.get_grid() == expected.get_grid());
+    }
+}
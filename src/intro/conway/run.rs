@@ -0,0 +1,237 @@
+#![forbid(unsafe_code)]
+
+use super::storage::{Dense, Storage};
+use super::{Cell, GameOfLife, Grid};
+use std::collections::VecDeque;
+
+////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct GenerationStats<'a> {
+    pub generation: usize,
+    pub population: usize,
+    pub births: usize,
+    pub deaths: usize,
+    pub changed: &'a [(usize, usize)],
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StopReason {
+    MaxGenerations,
+    Extinction,
+    // The board repeats the one `period` generations ago.
+    Stabilized { period: usize },
+    Predicate,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RunSummary {
+    pub reason: StopReason,
+    pub steps: usize,
+    pub generation: usize,
+    pub population: usize,
+    pub births: usize,
+    pub deaths: usize,
+}
+
+type Observer<'a> = Box<dyn FnMut(&GenerationStats) + 'a>;
+type Predicate<'a, S> = Box<dyn FnMut(&GameOfLife<S>) -> bool + 'a>;
+
+pub struct RunConfig<'a, S = Dense<Cell>> {
+    max_generations: usize,
+    extinction: bool,
+    stabilization: Option<usize>,
+    predicate: Option<Predicate<'a, S>>,
+    observers: Vec<Observer<'a>>,
+}
+
+impl<'a, S> RunConfig<'a, S> {
+    // Every run is bounded by the number of generations to step.
+    pub fn new(max_generations: usize) -> Self {
+        RunConfig {
+            max_generations,
+            extinction: false,
+            stabilization: None,
+            predicate: None,
+            observers: vec![],
+        }
+    }
+
+    pub fn stop_on_extinction(mut self) -> Self {
+        self.extinction = true;
+        self
+    }
+
+    // Stops once the board repeats itself with a period of at most
+    // `max_period`, keeping that many previous boards to compare with.
+    pub fn stop_on_stabilization(mut self, max_period: usize) -> Self {
+        self.stabilization = Some(max_period.max(1));
+        self
+    }
+
+    // Stops after the generation for which `predicate` returns true.
+    pub fn stop_when<P: FnMut(&GameOfLife<S>) -> bool + 'a>(mut self, predicate: P) -> Self {
+        self.predicate = Some(Box::new(predicate));
+        self
+    }
+
+    // Observers are called after every generation in the order they were added.
+    pub fn observe<O: FnMut(&GenerationStats) + 'a>(mut self, observer: O) -> Self {
+        self.observers.push(Box::new(observer));
+        self
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+impl<S: Storage<Cell> + Clone> GameOfLife<S> {
+    pub fn population(&self) -> usize {
+        self.grid
+            .storage()
+            .cells()
+            .filter(|(_, cell)| **cell == Cell::Alive)
+            .count()
+    }
+
+    pub fn run(&mut self, mut config: RunConfig<S>) -> RunSummary {
+        let mut summary = RunSummary {
+            reason: StopReason::MaxGenerations,
+            steps: 0,
+            generation: self.generation,
+            population: self.population(),
+            births: 0,
+            deaths: 0,
+        };
+        let mut previous: VecDeque<Grid<Cell, S>> = VecDeque::new();
+        while summary.steps < config.max_generations {
+            if let Some(max_period) = config.stabilization {
+                if previous.len() == max_period {
+                    previous.pop_back();
+                }
+                previous.push_front(self.grid.clone());
+            }
+            let changed = self.advance();
+            let births = changed
+                .iter()
+                .filter(|&&(row, col)| *self.grid.get(row, col) == Cell::Alive)
+                .count();
+            let deaths = changed.len() - births;
+            summary.steps += 1;
+            summary.generation = self.generation;
+            summary.population = summary.population + births - deaths;
+            summary.births += births;
+            summary.deaths += deaths;
+
+            let stats = GenerationStats {
+                generation: self.generation,
+                population: summary.population,
+                births,
+                deaths,
+                changed: &changed,
+            };
+            for observer in &mut config.observers {
+                observer(&stats);
+            }
+
+            if config.extinction && summary.population == 0 {
+                summary.reason = StopReason::Extinction;
+                return summary;
+            }
+            if let Some(period) = previous.iter().position(|grid| *grid == self.grid) {
+                summary.reason = StopReason::Stabilized { period: period + 1 };
+                return summary;
+            }
+            if let Some(predicate) = &mut config.predicate {
+                if predicate(self) {
+                    summary.reason = StopReason::Predicate;
+                    return summary;
+                }
+            }
+        }
+        summary
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{RunConfig, StopReason};
+    use crate::intro::conway::census::cells_from_rows;
+    use crate::intro::conway::{Cell, GameOfLife, Grid};
+
+    fn game(rows: usize, cols: usize, pattern: &[&str], row: usize, col: usize) -> GameOfLife {
+        let mut grid = Grid::new(rows, cols);
+        for (r, c) in cells_from_rows(pattern) {
+            grid.set(Cell::Alive, row + r, col + c);
+        }
+        GameOfLife::from_grid(grid)
+    }
+
+    #[test]
+    fn max_generations() {
+        let mut game = game(10, 10, &[".O.", "..O", "OOO"], 0, 0);
+        let summary = game.run(RunConfig::new(3).stop_on_extinction());
+        assert_eq!(summary.reason, StopReason::MaxGenerations);
+        assert_eq!(summary.steps, 3);
+        assert_eq!(summary.generation, 3);
+        assert_eq!(game.generation(), 3);
+        assert_eq!(summary.population, 5);
+
+        let summary = game.run(RunConfig::new(0));
+        assert_eq!(summary.steps, 0);
+        assert_eq!(summary.generation, 3);
+    }
+
+    #[test]
+    fn extinction() {
+        let mut game = game(5, 5, &["O.O"], 2, 1);
+        let summary = game.run(RunConfig::new(100).stop_on_extinction());
+        assert_eq!(summary.reason, StopReason::Extinction);
+        assert_eq!(summary.steps, 1);
+        assert_eq!(summary.population, 0);
+        assert_eq!(summary.deaths, 2);
+        assert_eq!(summary.births, 0);
+    }
+
+    #[test]
+    fn stabilization() {
+        let mut blinker = game(5, 5, &["OOO"], 2, 1);
+        let summary = blinker.run(RunConfig::new(100).stop_on_stabilization(4));
+        assert_eq!(summary.reason, StopReason::Stabilized { period: 2 });
+        assert_eq!(summary.steps, 2);
+
+        let mut block = game(6, 6, &["OO.", "O.."], 1, 1);
+        let summary = block.run(RunConfig::new(100).stop_on_stabilization(1));
+        assert_eq!(summary.reason, StopReason::Stabilized { period: 1 });
+        assert_eq!(summary.steps, 2);
+        assert_eq!(summary.population, 4);
+
+        let mut blinker = game(5, 5, &["OOO"], 2, 1);
+        let summary = blinker.run(RunConfig::new(10).stop_on_stabilization(1));
+        assert_eq!(summary.reason, StopReason::MaxGenerations);
+    }
+
+    #[test]
+    fn observers_and_predicate() {
+        let mut glider = game(12, 12, &[".O.", "..O", "OOO"], 0, 0);
+        let mut populations = vec![];
+        let mut changes = 0;
+        let mut generations = vec![];
+        let summary = glider.run(
+            RunConfig::new(100)
+                .observe(|stats| populations.push(stats.population))
+                .observe(|stats| {
+                    assert_eq!(stats.births + stats.deaths, stats.changed.len());
+                    changes += stats.changed.len();
+                    generations.push(stats.generation);
+                })
+                .stop_when(|game| *game.get_grid().get(5, 5) == Cell::Alive),
+        );
+        assert_eq!(summary.reason, StopReason::Predicate);
+        assert_eq!(populations, vec![5; summary.steps]);
+        assert_eq!(generations, (1..=summary.steps).collect::<Vec<_>>());
+        assert_eq!(changes, summary.births + summary.deaths);
+        assert_eq!(summary.births, summary.deaths);
+        assert_eq!(glider.population(), 5);
+        assert_eq!(*glider.get_grid().get(5, 5), Cell::Alive);
+    }
+}
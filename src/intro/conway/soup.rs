@@ -0,0 +1,422 @@
+#![forbid(unsafe_code)]
+
+use super::active::ActiveConfig;
+use super::census::Catalogue;
+use super::{Cell, GameOfLife, Grid};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::thread;
+
+////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SoupConfig {
+    // Side of the random square placed in the middle of the board.
+    pub soup_size: usize,
+    pub board_size: usize,
+    pub density_percent: u32,
+    pub soups: usize,
+    // Soup `i` is generated from `seed + i`, so any single soup can be
+    // reproduced regardless of the number of threads.
+    pub seed: u64,
+    pub threads: usize,
+    pub max_generations: usize,
+}
+
+impl Default for SoupConfig {
+    fn default() -> Self {
+        SoupConfig {
+            soup_size: 16,
+            board_size: 64,
+            density_percent: 50,
+            soups: 100,
+            seed: 0,
+            threads: 4,
+            max_generations: 2000,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SoupResult {
+    pub soup: usize,
+    pub seed: u64,
+    // Generation at which the board entered its final cycle, `None` when it
+    // did not settle within `max_generations`.
+    pub lifespan: Option<usize>,
+    pub period: Option<usize>,
+    pub final_population: usize,
+    pub census: BTreeMap<String, usize>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SoupReport {
+    pub results: Vec<SoupResult>,
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+pub fn soup(config: &SoupConfig, seed: u64) -> Grid<Cell> {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut grid = Grid::new(config.board_size, config.board_size);
+    let size = config.soup_size.min(config.board_size);
+    let offset = (config.board_size - size) / 2;
+    for row in offset..offset + size {
+        for col in offset..offset + size {
+            if rng.gen_range(0..100) < config.density_percent {
+                grid.set(Cell::Alive, row, col);
+            }
+        }
+    }
+    grid
+}
+
+pub fn search(config: &SoupConfig) -> SoupReport {
+    let next = AtomicUsize::new(0);
+    let results = Mutex::new(Vec::with_capacity(config.soups));
+    thread::scope(|scope| {
+        for _ in 0..config.threads.max(1) {
+            scope.spawn(|| loop {
+                let soup = next.fetch_add(1, Ordering::Relaxed);
+                if soup >= config.soups {
+                    break;
+                }
+                let result = run_soup(config, soup);
+                results.lock().unwrap().push(result);
+            });
+        }
+    });
+    let mut results = results.into_inner().unwrap();
+    results.sort_by_key(|result| result.soup);
+    SoupReport { results }
+}
+
+fn run_soup(config: &SoupConfig, soup: usize) -> SoupResult {
+    let seed = config.seed.wrapping_add(soup as u64);
+    let mut game = GameOfLife::from_grid(self::soup(config, seed));
+    game.enable_active_region(ActiveConfig::default());
+    let (lifespan, period) = match stabilize(&mut game, config.max_generations) {
+        Some((lifespan, period)) => (Some(lifespan), Some(period)),
+        None => (None, None),
+    };
+    let census = Catalogue::standard().census(game.get_grid());
+    SoupResult {
+        soup,
+        seed,
+        lifespan,
+        period,
+        final_population: census.objects().iter().map(|o| o.cells.len()).sum(),
+        census: census.counts().clone(),
+    }
+}
+
+// Steps until some board repeats, returning the generation the cycle starts
+// at and its period, and leaving the game at the first repeated board (or at
+// `max_generations`). Brent's algorithm finds the cycle while keeping only a
+// couple of boards, on copies of the game that replay it from the start.
+fn stabilize(game: &mut GameOfLife, max_generations: usize) -> Option<(usize, usize)> {
+    let start = game.generation();
+    let replay = || {
+        let mut copy = GameOfLife::from_grid(game.get_grid().clone());
+        copy.set_rule(game.rule().clone());
+        copy
+    };
+    let found = find_period(replay(), max_generations);
+    let end = start + max_generations;
+    let Some(period) = found.filter(|&period| period <= max_generations) else {
+        advance_to(game, end);
+        return None;
+    };
+    let mut tortoise = replay();
+    advance_to(game, start + period);
+    while tortoise.get_grid() != game.get_grid() {
+        if game.generation() == end {
+            return None;
+        }
+        tortoise.step();
+        game.step();
+    }
+    Some((start + tortoise.generation(), period))
+}
+
+// The tortoise waits at generations 2^k - 1 while the hare runs up to 2^k
+// steps ahead of it. A cycle that is entered and closed within
+// `max_generations` is found before the hare gets 4 times as far.
+fn find_period(mut hare: GameOfLife, max_generations: usize) -> Option<usize> {
+    let limit = 4 * (max_generations + 1);
+    let mut tortoise = hare.get_grid().clone();
+    let (mut power, mut period) = (1, 0);
+    while hare.generation() < limit {
+        hare.step();
+        period += 1;
+        if *hare.get_grid() == tortoise {
+            return Some(period);
+        }
+        if period == power {
+            tortoise = hare.get_grid().clone();
+            power *= 2;
+            period = 0;
+        }
+    }
+    None
+}
+
+fn advance_to(game: &mut GameOfLife, generation: usize) {
+    while game.generation() < generation {
+        game.step();
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+impl SoupReport {
+    // Objects over all final boards.
+    pub fn totals(&self) -> BTreeMap<String, usize> {
+        let mut res = BTreeMap::new();
+        for result in &self.results {
+            for (name, count) in &result.census {
+                *res.entry(name.clone()).or_insert(0) += count;
+            }
+        }
+        res
+    }
+
+    pub fn to_csv(&self) -> String {
+        let mut res = String::from("soup,seed,lifespan,period,final_population,census\n");
+        for result in &self.results {
+            let census: Vec<String> = result
+                .census
+                .iter()
+                .map(|(name, count)| format!("{}:{}", name, count))
+                .collect();
+            writeln!(
+                res,
+                "{},{},{},{},{},\"{}\"",
+                result.soup,
+                result.seed,
+                optional(result.lifespan, ""),
+                optional(result.period, ""),
+                result.final_population,
+                census.join(";").replace('"', "\"\"")
+            )
+            .unwrap();
+        }
+        res
+    }
+
+    pub fn to_json(&self) -> String {
+        let results: Vec<String> = self
+            .results
+            .iter()
+            .map(|result| {
+                format!(
+                    "{{\"soup\":{},\"seed\":{},\"lifespan\":{},\"period\":{},\"final_population\":{},\"census\":{}}}",
+                    result.soup,
+                    result.seed,
+                    optional(result.lifespan, "null"),
+                    optional(result.period, "null"),
+                    result.final_population,
+                    json_counts(&result.census)
+                )
+            })
+            .collect();
+        format!(
+            "{{\"results\":[{}],\"totals\":{}}}",
+            results.join(","),
+            json_counts(&self.totals())
+        )
+    }
+}
+
+fn optional(value: Option<usize>, none: &str) -> String {
+    value.map_or_else(|| none.to_string(), |value| value.to_string())
+}
+
+fn json_counts(counts: &BTreeMap<String, usize>) -> String {
+    let entries: Vec<String> = counts
+        .iter()
+        .map(|(name, count)| format!("\"{}\":{}", json_escape(name), count))
+        .collect();
+    format!("{{{}}}", entries.join(","))
+}
+
+fn json_escape(s: &str) -> String {
+    let mut res = String::with_capacity(s.len());
+    for ch in s.chars() {
+        match ch {
+            '"' => res.push_str("\\\""),
+            '\\' => res.push_str("\\\\"),
+            ch if (ch as u32) < 0x20 => write!(res, "\\u{:04x}", ch as u32).unwrap(),
+            ch => res.push(ch),
+        }
+    }
+    res
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{search, soup, stabilize, SoupConfig, SoupReport, SoupResult};
+    use crate::intro::conway::{Cell, GameOfLife, Grid};
+    use std::collections::{BTreeMap, HashMap};
+
+    fn config() -> SoupConfig {
+        SoupConfig {
+            soup_size: 8,
+            board_size: 32,
+            soups: 12,
+            seed: 42,
+            threads: 3,
+            max_generations: 500,
+            ..SoupConfig::default()
+        }
+    }
+
+    #[test]
+    fn soups_are_reproducible() {
+        let config = config();
+        let first = soup(&config, 7);
+        assert!(first == soup(&config, 7));
+        assert!(first != soup(&config, 8));
+        for row in 0..32 {
+            for col in 0..32 {
+                let inside = (12..20).contains(&row) && (12..20).contains(&col);
+                if !inside {
+                    assert!(*first.get(row, col) == Cell::Dead);
+                }
+            }
+        }
+
+        let report = search(&config);
+        let single = search(&SoupConfig {
+            threads: 1,
+            ..config
+        });
+        assert_eq!(report, single);
+        assert_eq!(report.results.len(), 12);
+        for (i, result) in report.results.iter().enumerate() {
+            assert_eq!(result.soup, i);
+            assert_eq!(result.seed, 42 + i as u64);
+        }
+    }
+
+    #[test]
+    fn results_describe_final_board() {
+        let config = config();
+        for result in search(&config).results {
+            let mut game = GameOfLife::from_grid(soup(&config, result.seed));
+            let settled = stabilize(&mut game, config.max_generations);
+            assert_eq!(result.lifespan, settled.map(|s| s.0));
+            assert_eq!(result.period, settled.map(|s| s.1));
+            let census = game.census();
+            assert_eq!(&result.census, census.counts());
+            let population = (0..32)
+                .flat_map(|row| (0..32).map(move |col| (row, col)))
+                .filter(|&(row, col)| *game.get_grid().get(row, col) == Cell::Alive)
+                .count();
+            assert_eq!(result.final_population, population);
+        }
+    }
+
+    #[test]
+    fn stabilization() {
+        let mut grid = Grid::new(5, 5);
+        for col in 1..4 {
+            grid.set(Cell::Alive, 2, col);
+        }
+        let mut game = GameOfLife::from_grid(grid);
+        assert_eq!(stabilize(&mut game, 10), Some((0, 2)));
+
+        let mut grid = Grid::new(4, 4);
+        grid.set(Cell::Alive, 1, 1);
+        let mut game = GameOfLife::from_grid(grid);
+        assert_eq!(stabilize(&mut game, 10), Some((1, 1)));
+    }
+
+    // Remembers every board.
+    fn stabilize_naive(game: &mut GameOfLife, max_generations: usize) -> Option<(usize, usize)> {
+        let mut seen = HashMap::new();
+        seen.insert(game.get_grid().clone(), game.generation());
+        while game.generation() < max_generations {
+            game.step();
+            if let Some(&start) = seen.get(game.get_grid()) {
+                return Some((start, game.generation() - start));
+            }
+            seen.insert(game.get_grid().clone(), game.generation());
+        }
+        None
+    }
+
+    #[test]
+    fn compare_with_naive_stabilization() {
+        let config = SoupConfig {
+            soup_size: 6,
+            board_size: 12,
+            ..config()
+        };
+        for seed in 0..30 {
+            let mut naive = GameOfLife::from_grid(soup(&config, seed));
+            let expected = stabilize_naive(&mut naive, 300);
+            // Limits right around the end of the first cycle as well.
+            let ends = expected.map_or(vec![], |(start, period)| {
+                vec![start + period - 1, start + period, start + period + 1]
+            });
+            for max_generations in ends.into_iter().chain([0, 1, 7, 300]) {
+                let mut naive = GameOfLife::from_grid(soup(&config, seed));
+                let mut game = GameOfLife::from_grid(soup(&config, seed));
+                assert_eq!(
+                    stabilize(&mut game, max_generations),
+                    stabilize_naive(&mut naive, max_generations)
+                );
+                assert_eq!(game.generation(), naive.generation());
+                assert!(game.get_grid() == naive.get_grid());
+            }
+        }
+    }
+
+    #[test]
+    fn report_formats() {
+        let report = SoupReport {
+            results: vec![
+                SoupResult {
+                    soup: 0,
+                    seed: 5,
+                    lifespan: Some(120),
+                    period: Some(2),
+                    final_population: 7,
+                    census: BTreeMap::from([("block".to_string(), 1), ("blinker".to_string(), 1)]),
+                },
+                SoupResult {
+                    soup: 1,
+                    seed: 6,
+                    lifespan: None,
+                    period: None,
+                    final_population: 4,
+                    census: BTreeMap::from([("block".to_string(), 1)]),
+                },
+            ],
+        };
+        assert_eq!(
+            report.totals(),
+            BTreeMap::from([("block".to_string(), 2), ("blinker".to_string(), 1)])
+        );
+        assert_eq!(
+            report.to_csv(),
+            "soup,seed,lifespan,period,final_population,census\n\
+             0,5,120,2,7,\"blinker:1;block:1\"\n\
+             1,6,,,4,\"block:1\"\n"
+        );
+        assert_eq!(
+            report.to_json(),
+            "{\"results\":[\
+             {\"soup\":0,\"seed\":5,\"lifespan\":120,\"period\":2,\"final_population\":7,\
+             \"census\":{\"blinker\":1,\"block\":1}},\
+             {\"soup\":1,\"seed\":6,\"lifespan\":null,\"period\":null,\"final_population\":4,\
+             \"census\":{\"block\":1}}],\
+             \"totals\":{\"blinker\":1,\"block\":2}}"
+        );
+    }
+}
@@ -0,0 +1,356 @@
+#![forbid(unsafe_code)]
+
+use std::collections::HashMap;
+
+////////////////////////////////////////////////////////////////////////////////
+
+// Cells of a `Grid`. A new storage is filled with `T::default()`; the grid
+// checks that coordinates are in range before calling it.
+pub trait Storage<T> {
+    // Whether `cells` skips most default values, so that walking it is
+    // cheaper than visiting every position of the grid.
+    const SPARSE: bool = false;
+
+    fn new(rows: usize, cols: usize) -> Self;
+    fn get(&self, row: usize, col: usize) -> &T;
+    fn set(&mut self, value: T, row: usize, col: usize);
+    // Every cell that may differ from `T::default()`, once each and in no
+    // particular order, with its `(row, col)`; the rest are default.
+    fn cells<'a>(&'a self) -> impl Iterator<Item = ((usize, usize), &'a T)>
+    where
+        T: 'a;
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Clone, Debug)]
+pub struct Dense<T> {
+    cols: usize,
+    cells: Vec<T>,
+}
+
+impl<T: Clone + Default> Storage<T> for Dense<T> {
+    fn new(rows: usize, cols: usize) -> Self {
+        Dense {
+            cols,
+            cells: vec![T::default(); rows * cols],
+        }
+    }
+
+    fn get(&self, row: usize, col: usize) -> &T {
+        &self.cells[col + row * self.cols]
+    }
+
+    fn set(&mut self, value: T, row: usize, col: usize) {
+        self.cells[col + row * self.cols] = value;
+    }
+
+    fn cells<'a>(&'a self) -> impl Iterator<Item = ((usize, usize), &'a T)>
+    where
+        T: 'a,
+    {
+        self.cells
+            .iter()
+            .enumerate()
+            .map(|(i, value)| ((i / self.cols, i % self.cols), value))
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+// Keeps only the cells that differ from the default value.
+#[derive(Clone, Debug)]
+pub struct Sparse<T> {
+    default: T,
+    cells: HashMap<(usize, usize), T>,
+}
+
+impl<T> Sparse<T> {
+    pub fn len(&self) -> usize {
+        self.cells.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cells.is_empty()
+    }
+}
+
+impl<T: Default + PartialEq> Storage<T> for Sparse<T> {
+    const SPARSE: bool = true;
+
+    fn new(_rows: usize, _cols: usize) -> Self {
+        Sparse {
+            default: T::default(),
+            cells: HashMap::new(),
+        }
+    }
+
+    fn get(&self, row: usize, col: usize) -> &T {
+        self.cells.get(&(row, col)).unwrap_or(&self.default)
+    }
+
+    fn set(&mut self, value: T, row: usize, col: usize) {
+        if value == self.default {
+            self.cells.remove(&(row, col));
+        } else {
+            self.cells.insert((row, col), value);
+        }
+    }
+
+    fn cells<'a>(&'a self) -> impl Iterator<Item = ((usize, usize), &'a T)>
+    where
+        T: 'a,
+    {
+        self.cells
+            .iter()
+            .map(|(&position, value)| (position, value))
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+pub const CHUNK_SIZE: usize = 16;
+
+// Square tiles of `CHUNK_SIZE` cells on a side, allocated on the first write
+// of a non-default value.
+#[derive(Clone, Debug)]
+pub struct Chunked<T> {
+    rows: usize,
+    cols: usize,
+    default: T,
+    chunks: HashMap<(usize, usize), Vec<T>>,
+}
+
+impl<T> Chunked<T> {
+    pub fn chunks(&self) -> usize {
+        self.chunks.len()
+    }
+}
+
+impl<T: Clone + Default + PartialEq> Storage<T> for Chunked<T> {
+    const SPARSE: bool = true;
+
+    fn new(rows: usize, cols: usize) -> Self {
+        Chunked {
+            rows,
+            cols,
+            default: T::default(),
+            chunks: HashMap::new(),
+        }
+    }
+
+    fn get(&self, row: usize, col: usize) -> &T {
+        match self.chunks.get(&(row / CHUNK_SIZE, col / CHUNK_SIZE)) {
+            Some(chunk) => &chunk[col % CHUNK_SIZE + row % CHUNK_SIZE * CHUNK_SIZE],
+            None => &self.default,
+        }
+    }
+
+    fn set(&mut self, value: T, row: usize, col: usize) {
+        let key = (row / CHUNK_SIZE, col / CHUNK_SIZE);
+        let chunk = match self.chunks.get_mut(&key) {
+            Some(chunk) => chunk,
+            None if value == self.default => return,
+            None => self
+                .chunks
+                .entry(key)
+                .or_insert_with(|| vec![T::default(); CHUNK_SIZE * CHUNK_SIZE]),
+        };
+        chunk[col % CHUNK_SIZE + row % CHUNK_SIZE * CHUNK_SIZE] = value;
+    }
+
+    // Chunks on the edges stick out of the grid.
+    fn cells<'a>(&'a self) -> impl Iterator<Item = ((usize, usize), &'a T)>
+    where
+        T: 'a,
+    {
+        self.chunks
+            .iter()
+            .flat_map(move |(&(chunk_row, chunk_col), chunk)| {
+                chunk
+                    .iter()
+                    .enumerate()
+                    .map(move |(i, value)| {
+                        let row = chunk_row * CHUNK_SIZE + i / CHUNK_SIZE;
+                        let col = chunk_col * CHUNK_SIZE + i % CHUNK_SIZE;
+                        ((row, col), value)
+                    })
+                    .filter(|&((row, col), _)| row < self.rows && col < self.cols)
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Chunked, Dense, Sparse, Storage, CHUNK_SIZE};
+    use crate::intro::conway::active::ActiveConfig;
+    use crate::intro::conway::census::cells_from_rows;
+    use crate::intro::conway::path::Neighbourhood;
+    use crate::intro::conway::{Cell, GameOfLife, Grid};
+    use rand::Rng;
+    use std::collections::HashSet;
+
+    fn same_operations<S: Storage<u8>>() {
+        let mut rng = rand::thread_rng();
+        let mut dense = Grid::<u8>::new(40, 50);
+        let mut other = Grid::<u8, S>::with_storage(40, 50);
+        for _ in 0..2000 {
+            let (row, col) = (rng.gen_range(0..40), rng.gen_range(0..50));
+            let value = rng.gen_range(0..3);
+            dense.set(value, row, col);
+            other.set(value, row, col);
+        }
+        for row in 0..40 {
+            for col in 0..50 {
+                assert_eq!(dense.get(row, col), other.get(row, col));
+            }
+        }
+        assert_eq!(
+            dense.neighbours(0, 49).collect::<Vec<_>>(),
+            other.neighbours(0, 49).collect::<Vec<_>>()
+        );
+        assert!(other.to_storage::<Dense<u8>>() == dense);
+        assert!(dense.to_storage::<S>() == other);
+    }
+
+    #[test]
+    fn backends_agree() {
+        same_operations::<Dense<u8>>();
+        same_operations::<Sparse<u8>>();
+        same_operations::<Chunked<u8>>();
+    }
+
+    #[test]
+    fn sparse_keeps_non_default_cells() {
+        let mut grid = Grid::<u8, Sparse<u8>>::with_storage(1 << 30, 1 << 30);
+        grid.set(5, 1 << 29, 12345);
+        grid.set(7, 0, 0);
+        grid.set(0, 0, 0);
+        grid.set(0, 3, 3);
+        assert_eq!(grid.storage().len(), 1);
+        assert_eq!(*grid.get(1 << 29, 12345), 5);
+        assert_eq!(*grid.get(0, 0), 0);
+        assert_eq!(
+            grid.storage().cells().collect::<Vec<_>>(),
+            vec![((1 << 29, 12345), &5)]
+        );
+    }
+
+    #[test]
+    fn chunks_are_allocated_on_demand() {
+        let mut grid = Grid::<Cell, Chunked<Cell>>::with_storage(1 << 30, 1 << 30);
+        grid.set(Cell::Dead, 100, 100);
+        assert_eq!(grid.storage().chunks(), 0);
+        let mut chunks = HashSet::new();
+        for i in 0..CHUNK_SIZE * 3 {
+            grid.set(Cell::Alive, 1 << 20, (1 << 20) + i);
+            chunks.insert(((1 << 20) + i) / CHUNK_SIZE);
+        }
+        assert_eq!(grid.storage().chunks(), chunks.len());
+        assert_eq!(*grid.get(1 << 20, (1 << 20) + 5), Cell::Alive);
+        assert_eq!(*grid.get((1 << 20) + 1, (1 << 20) + 5), Cell::Dead);
+    }
+
+    #[test]
+    fn equality_ignores_representation() {
+        let mut first = Grid::<Cell, Chunked<Cell>>::with_storage(20, 20);
+        let second = first.clone();
+        first.set(Cell::Alive, 3, 4);
+        assert!(first != second);
+        first.set(Cell::Dead, 3, 4);
+        assert_eq!(first.storage().chunks(), 1);
+        assert!(first == second);
+
+        let mut blinker = Grid::<Cell, Sparse<Cell>>::with_storage(6, 6);
+        for col in 1..4 {
+            blinker.set(Cell::Alive, 2, col);
+        }
+        let mut game = GameOfLife::with_storage(blinker.clone());
+        game.step();
+        game.step();
+        assert!(game.get_grid() == &blinker);
+    }
+
+    // The glider crosses a chunk border on the way.
+    fn glider_agrees<S: Storage<Cell> + Clone>() {
+        let mut grid = Grid::new(24, 24);
+        for (row, col) in cells_from_rows(&[".O.", "..O", "OOO"]) {
+            grid.set(Cell::Alive, row + 2, col + 2);
+        }
+        let mut dense = GameOfLife::from_grid(grid.clone());
+        let mut other = GameOfLife::with_storage(grid.to_storage::<S>());
+        for _ in 0..60 {
+            dense.step();
+            other.step();
+            assert!(other.get_grid().to_storage::<Dense<Cell>>() == *dense.get_grid());
+            assert_eq!(other.population(), 5);
+        }
+        assert_eq!(other.census().count("glider"), 1);
+    }
+
+    #[test]
+    fn glider_on_sparse() {
+        glider_agrees::<Sparse<Cell>>();
+    }
+
+    #[test]
+    fn glider_on_chunked() {
+        glider_agrees::<Chunked<Cell>>();
+    }
+
+    #[test]
+    fn step_huge_sparse_world() {
+        let mut grid = Grid::<Cell, Sparse<Cell>>::with_storage(1 << 30, 1 << 30);
+        for (row, col) in cells_from_rows(&[".O.", "..O", "OOO"]) {
+            grid.set(Cell::Alive, row + (1 << 29), col + (1 << 29));
+        }
+        let mut game = GameOfLife::with_storage(grid.clone());
+        let mut active = GameOfLife::with_storage(grid);
+        active.enable_active_region(ActiveConfig::default());
+        for _ in 0..40 {
+            game.step();
+            active.step();
+        }
+        assert_eq!(game.get_grid().storage().len(), 5);
+        assert_eq!(
+            *game.get_grid().get((1 << 29) + 12, (1 << 29) + 11),
+            Cell::Alive
+        );
+        assert_eq!(
+            game.get_grid().storage().cells().collect::<HashSet<_>>(),
+            active.get_grid().storage().cells().collect::<HashSet<_>>()
+        );
+    }
+
+    #[test]
+    fn cells_cover_non_default_values() {
+        let mut grid = Grid::<u8, Chunked<u8>>::with_storage(20, 20);
+        grid.set(1, 19, 19);
+        grid.set(2, 0, 3);
+        let mut cells: Vec<_> = grid
+            .storage()
+            .cells()
+            .filter(|(_, value)| **value != 0)
+            .collect();
+        cells.sort();
+        assert_eq!(cells, [((0, 3), &2), ((19, 19), &1)]);
+        assert_eq!(grid.storage().cells().count(), 16 * 16 + 4 * 4);
+        assert_eq!(
+            grid.to_storage::<Dense<u8>>().storage().cells().count(),
+            400
+        );
+    }
+
+    #[test]
+    fn search_huge_sparse_world() {
+        let mut grid = Grid::<u8, Sparse<u8>>::with_storage(1 << 30, 1 << 30);
+        for row in 0..10 {
+            grid.set(1, row, 5);
+        }
+        let path = grid
+            .bfs((3, 3), (3, 7), Neighbourhood::Four, |&value| value == 0)
+            .unwrap();
+        assert_eq!(path.len(), 7 + 4 + 7 + 1);
+        assert_eq!(grid.region((0, 5), Neighbourhood::Four).len(), 10);
+    }
+}
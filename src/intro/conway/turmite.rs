@@ -0,0 +1,434 @@
+#![forbid(unsafe_code)]
+
+use super::Grid;
+use std::str::FromStr;
+use thiserror::Error;
+
+////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Turn {
+    None,
+    Right,
+    UTurn,
+    Left,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Heading {
+    North,
+    East,
+    South,
+    West,
+}
+
+impl Heading {
+    pub fn turn(self, turn: Turn) -> Self {
+        let quarters = match turn {
+            Turn::None => 0,
+            Turn::Right => 1,
+            Turn::UTurn => 2,
+            Turn::Left => 3,
+        };
+        let headings = [Heading::North, Heading::East, Heading::South, Heading::West];
+        headings[(self as usize + quarters) % 4]
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Transition {
+    pub write: u8,
+    pub turn: Turn,
+    pub next_state: usize,
+}
+
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum RuleError {
+    #[error("rule is empty")]
+    Empty,
+    #[error("unknown turn {0:?}")]
+    UnknownTurn(String),
+    #[error("too many colors")]
+    TooManyColors,
+    #[error("malformed rule near {0:?}")]
+    Syntax(String),
+    #[error("state {state} has {found} colors instead of {expected}")]
+    ColorCount {
+        state: usize,
+        expected: usize,
+        found: usize,
+    },
+    #[error("transition refers to missing color {0}")]
+    MissingColor(usize),
+    #[error("transition refers to missing state {0}")]
+    MissingState(usize),
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+// `table[state * colors + color]` says what a turmite in `state` does on a
+// cell of `color`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TransitionTable {
+    states: usize,
+    colors: usize,
+    table: Vec<Transition>,
+}
+
+impl TransitionTable {
+    // Langton's ant notation: the letter at position `c` is the turn taken on a
+    // cell of color `c`, after which the cell gets color `c + 1` (cyclically).
+    // Letters are R, L, N (no turn) and U (u-turn).
+    pub fn ant(rule: &str) -> Result<Self, RuleError> {
+        let rule = rule.trim();
+        if rule.is_empty() {
+            return Err(RuleError::Empty);
+        }
+        let turns = rule
+            .chars()
+            .map(|ch| match ch.to_ascii_uppercase() {
+                'N' => Ok(Turn::None),
+                'R' => Ok(Turn::Right),
+                'U' => Ok(Turn::UTurn),
+                'L' => Ok(Turn::Left),
+                _ => Err(RuleError::UnknownTurn(ch.to_string())),
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        if turns.len() > u8::MAX as usize + 1 {
+            return Err(RuleError::TooManyColors);
+        }
+        let colors = turns.len();
+        let table = turns
+            .into_iter()
+            .enumerate()
+            .map(|(color, turn)| Transition {
+                write: ((color + 1) % colors) as u8,
+                turn,
+                next_state: 0,
+            })
+            .collect();
+        Ok(TransitionTable {
+            states: 1,
+            colors,
+            table,
+        })
+    }
+
+    // 2D Turing machine notation, e.g. `{{{1,8,1},{1,8,1}},{{1,2,1},{0,1,0}}}`:
+    // a list of states, each a list of `{write, turn, next state}` per color,
+    // where turns are 1 (none), 2 (right), 4 (u-turn) and 8 (left).
+    pub fn turmite(rule: &str) -> Result<Self, RuleError> {
+        let rule: String = rule.chars().filter(|ch| !ch.is_whitespace()).collect();
+        if rule.is_empty() {
+            return Err(RuleError::Empty);
+        }
+        let body = rule
+            .strip_prefix("{{{")
+            .and_then(|rule| rule.strip_suffix("}}}"))
+            .ok_or_else(|| RuleError::Syntax(rule.clone()))?;
+
+        let mut rows = vec![];
+        for state in body.split("}},{{") {
+            let mut row = vec![];
+            for triple in state.split("},{") {
+                let numbers = triple
+                    .split(',')
+                    .map(|number| {
+                        number
+                            .parse::<usize>()
+                            .map_err(|_| RuleError::Syntax(triple.to_string()))
+                    })
+                    .collect::<Result<Vec<_>, _>>()?;
+                let [write, turn, next_state] = numbers[..] else {
+                    return Err(RuleError::Syntax(triple.to_string()));
+                };
+                let turn = match turn {
+                    1 => Turn::None,
+                    2 => Turn::Right,
+                    4 => Turn::UTurn,
+                    8 => Turn::Left,
+                    _ => return Err(RuleError::UnknownTurn(turn.to_string())),
+                };
+                row.push((write, turn, next_state));
+            }
+            rows.push(row);
+        }
+
+        let (states, colors) = (rows.len(), rows[0].len());
+        if colors > u8::MAX as usize + 1 {
+            return Err(RuleError::TooManyColors);
+        }
+        let mut table = Vec::with_capacity(states * colors);
+        for (state, row) in rows.into_iter().enumerate() {
+            if row.len() != colors {
+                return Err(RuleError::ColorCount {
+                    state,
+                    expected: colors,
+                    found: row.len(),
+                });
+            }
+            for (write, turn, next_state) in row {
+                if write >= colors {
+                    return Err(RuleError::MissingColor(write));
+                }
+                if next_state >= states {
+                    return Err(RuleError::MissingState(next_state));
+                }
+                table.push(Transition {
+                    write: write as u8,
+                    turn,
+                    next_state,
+                });
+            }
+        }
+        Ok(TransitionTable {
+            states,
+            colors,
+            table,
+        })
+    }
+
+    pub fn states(&self) -> usize {
+        self.states
+    }
+
+    pub fn colors(&self) -> usize {
+        self.colors
+    }
+
+    pub fn get(&self, state: usize, color: u8) -> Transition {
+        self.table[state * self.colors + color as usize]
+    }
+}
+
+impl FromStr for TransitionTable {
+    type Err = RuleError;
+
+    fn from_str(rule: &str) -> Result<Self, Self::Err> {
+        if rule.trim_start().starts_with('{') {
+            Self::turmite(rule)
+        } else {
+            Self::ant(rule)
+        }
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Turmite {
+    pub row: usize,
+    pub col: usize,
+    pub heading: Heading,
+    pub state: usize,
+}
+
+impl Turmite {
+    pub fn new(row: usize, col: usize, heading: Heading) -> Self {
+        Turmite {
+            row,
+            col,
+            heading,
+            state: 0,
+        }
+    }
+}
+
+// Turmites walk on a grid of colors that wraps around at the edges.
+pub struct TurmiteWorld {
+    grid: Grid<u8>,
+    table: TransitionTable,
+    turmites: Vec<Turmite>,
+    steps: usize,
+}
+
+impl TurmiteWorld {
+    pub fn new(grid: Grid<u8>, table: TransitionTable) -> Self {
+        TurmiteWorld {
+            grid,
+            table,
+            turmites: vec![],
+            steps: 0,
+        }
+    }
+
+    pub fn add(&mut self, turmite: Turmite) {
+        if turmite.row >= self.grid.rows || turmite.col >= self.grid.cols {
+            panic!("Турмит за пределами поля");
+        }
+        if turmite.state >= self.table.states() {
+            panic!("Неверное состояние");
+        }
+        self.turmites.push(turmite);
+    }
+
+    pub fn get_grid(&self) -> &Grid<u8> {
+        &self.grid
+    }
+
+    pub fn turmites(&self) -> &[Turmite] {
+        &self.turmites
+    }
+
+    pub fn steps(&self) -> usize {
+        self.steps
+    }
+
+    // Moves every turmite once, in the order they were added.
+    pub fn step(&mut self) {
+        let (rows, cols) = (self.grid.rows, self.grid.cols);
+        for turmite in &mut self.turmites {
+            let color = *self.grid.get(turmite.row, turmite.col);
+            if color as usize >= self.table.colors() {
+                panic!("Неверный цвет");
+            }
+            let transition = self.table.get(turmite.state, color);
+            self.grid.set(transition.write, turmite.row, turmite.col);
+            turmite.heading = turmite.heading.turn(transition.turn);
+            turmite.state = transition.next_state;
+            match turmite.heading {
+                Heading::North => turmite.row = (turmite.row + rows - 1) % rows,
+                Heading::East => turmite.col = (turmite.col + 1) % cols,
+                Heading::South => turmite.row = (turmite.row + 1) % rows,
+                Heading::West => turmite.col = (turmite.col + cols - 1) % cols,
+            }
+        }
+        self.steps += 1;
+    }
+
+    pub fn run(&mut self, steps: usize) {
+        for _ in 0..steps {
+            self.step();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Heading, RuleError, Transition, TransitionTable, Turmite, TurmiteWorld, Turn};
+    use crate::intro::conway::Grid;
+
+    fn world(rule: &str, size: usize) -> TurmiteWorld {
+        let mut world = TurmiteWorld::new(Grid::new(size, size), rule.parse().unwrap());
+        world.add(Turmite::new(size / 2, size / 2, Heading::North));
+        world
+    }
+
+    #[test]
+    fn langtons_ant() {
+        let mut world = world("RL", 5);
+        let positions = [(2, 3), (3, 3), (3, 2), (2, 2), (2, 1)];
+        let headings = [
+            Heading::East,
+            Heading::South,
+            Heading::West,
+            Heading::North,
+            Heading::West,
+        ];
+        for (position, heading) in positions.into_iter().zip(headings) {
+            world.step();
+            let ant = world.turmites()[0];
+            assert_eq!((ant.row, ant.col), position);
+            assert_eq!(ant.heading, heading);
+        }
+        assert_eq!(world.steps(), 5);
+        let grid = world.get_grid();
+        assert_eq!(*grid.get(2, 2), 0);
+        assert_eq!(*grid.get(2, 3), 1);
+        assert_eq!(*grid.get(3, 3), 1);
+        assert_eq!(*grid.get(3, 2), 1);
+    }
+
+    #[test]
+    fn notations_agree() {
+        let pairs = [
+            ("RL", "{{{1,2,0},{0,8,0}}}"),
+            ("RLR", "{{{1,2,0},{2,8,0},{0,2,0}}}"),
+            ("LLRR", "{{{1,8,0},{2,8,0},{3,2,0},{0,2,0}}}"),
+        ];
+        for (ant, turmite) in pairs {
+            let mut first = world(ant, 15);
+            let mut second = world(turmite, 15);
+            for _ in 0..1000 {
+                first.step();
+                second.step();
+                assert_eq!(first.turmites(), second.turmites());
+            }
+            assert!(first.get_grid() == second.get_grid());
+        }
+    }
+
+    #[test]
+    fn parse() {
+        let table: TransitionTable = "rlr".parse().unwrap();
+        assert_eq!((table.states(), table.colors()), (1, 3));
+        assert_eq!(
+            table.get(0, 2),
+            Transition {
+                write: 0,
+                turn: Turn::Right,
+                next_state: 0
+            }
+        );
+
+        let table =
+            TransitionTable::turmite("{{{1, 8, 1}, {1, 8, 1}}, {{1, 2, 1}, {0, 1, 0}}}").unwrap();
+        assert_eq!((table.states(), table.colors()), (2, 2));
+        assert_eq!(
+            table.get(1, 1),
+            Transition {
+                write: 0,
+                turn: Turn::None,
+                next_state: 0
+            }
+        );
+
+        assert_eq!(TransitionTable::ant(""), Err(RuleError::Empty));
+        assert_eq!(
+            TransitionTable::ant("RXL"),
+            Err(RuleError::UnknownTurn("X".to_string()))
+        );
+        assert_eq!(
+            TransitionTable::turmite("{{{1,3,0},{0,8,0}}}"),
+            Err(RuleError::UnknownTurn("3".to_string()))
+        );
+        assert_eq!(
+            TransitionTable::turmite("{{{2,2,0},{0,8,0}}}"),
+            Err(RuleError::MissingColor(2))
+        );
+        assert_eq!(
+            TransitionTable::turmite("{{{1,2,1},{0,8,0}}}"),
+            Err(RuleError::MissingState(1))
+        );
+        assert_eq!(
+            TransitionTable::turmite("{{{1,2,1},{0,8,0}},{{1,2,0}}}"),
+            Err(RuleError::ColorCount {
+                state: 1,
+                expected: 2,
+                found: 1
+            })
+        );
+        assert!(matches!(
+            TransitionTable::turmite("{{1,2,0}}"),
+            Err(RuleError::Syntax(_))
+        ));
+        assert!(matches!(
+            TransitionTable::turmite("{{{1,2},{0,8,0}}}"),
+            Err(RuleError::Syntax(_))
+        ));
+    }
+
+    #[test]
+    fn several_turmites() {
+        let mut world = TurmiteWorld::new(Grid::new(4, 4), "RL".parse().unwrap());
+        world.add(Turmite::new(0, 0, Heading::North));
+        world.add(Turmite::new(0, 1, Heading::West));
+        world.step();
+        // Turmites move one after another, so the second one already stands on
+        // the cell painted by the first, and it wraps around to the last row.
+        assert_eq!(world.turmites()[0], Turmite::new(0, 1, Heading::East));
+        assert_eq!((world.turmites()[1].row, world.turmites()[1].col), (3, 1));
+        world.run(99);
+        assert_eq!(world.steps(), 100);
+    }
+}
@@ -1,21 +1,159 @@
 #![forbid(unsafe_code)]
 
-#[derive(Default)]
+pub mod aggregator;
+pub mod bounded;
+pub mod persistent;
+pub mod quantile;
+pub mod range;
+pub mod realtime;
+pub mod rollback;
+pub mod sliding;
+pub mod sync;
+pub mod timed;
+
+#[cfg(test)]
+mod counted;
+
+use aggregator::{IntoValues, Iter, Min, SlidingAggregator};
+use rollback::Journal;
+use std::fmt::{self, Debug, Formatter};
+use std::ops::Sub;
+use std::{slice, vec};
+
+#[derive(Clone, Default)]
 pub struct MinStack<T> {
-    stack: Vec<(T, T)>, // .0 value .1 min
+    stack: Vec<T>,
+    // Indices of the elements that are smaller than everything below them,
+    // so the last one is the index of the minimum.
+    mins: Vec<usize>,
+    // Changes made since the oldest open checkpoint, see `rollback`.
+    journal: Journal<T>,
+}
+
+impl<T: Ord> MinStack<T> {
+    pub fn new() -> Self {
+        MinStack {
+            stack: vec![],
+            mins: vec![],
+            journal: Journal::new(),
+        }
+    }
+
+    pub fn push(&mut self, val: T) {
+        self.journal.pushed();
+        self.put(val);
+    }
+
+    pub fn pop(&mut self) -> Option<T> {
+        let val = self.take()?;
+        self.journal.popped(&val);
+        Some(val)
+    }
+
+    // `put` and `take` bypass the journal, so that rolling back is not
+    // journaled itself.
+    fn put(&mut self, val: T) {
+        if self.min().is_none_or(|min| val < *min) {
+            self.mins.push(self.stack.len());
+        }
+        self.stack.push(val);
+    }
+
+    fn take(&mut self) -> Option<T> {
+        let val = self.stack.pop()?;
+        if self.mins.last() == Some(&self.stack.len()) {
+            self.mins.pop();
+        }
+        Some(val)
+    }
+
+    pub fn peek(&self) -> Option<&T> {
+        self.stack.last()
+    }
+
+    pub fn min(&self) -> Option<&T> {
+        self.mins.last().map(|&i| &self.stack[i])
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.stack.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.stack.len()
+    }
+
+    // Values from the bottom to the top.
+    pub fn iter(&self) -> slice::Iter<'_, T> {
+        self.stack.iter()
+    }
+}
+
+impl<T: PartialEq> PartialEq for MinStack<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.stack == other.stack
+    }
+}
+
+impl<T: Eq> Eq for MinStack<T> {}
+
+impl<T: Debug> Debug for MinStack<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(&self.stack).finish()
+    }
+}
+
+impl<T: Ord> Extend<T> for MinStack<T> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for val in iter {
+            self.push(val);
+        }
+    }
+}
+
+impl<T: Ord> FromIterator<T> for MinStack<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut res = Self::new();
+        res.extend(iter);
+        res
+    }
+}
+
+impl<T> IntoIterator for MinStack<T> {
+    type Item = T;
+    type IntoIter = vec::IntoIter<T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.stack.into_iter()
+    }
 }
 
-impl<T: Clone + Ord> MinStack<T> {
+impl<'a, T: Ord> IntoIterator for &'a MinStack<T> {
+    type Item = &'a T;
+    type IntoIter = slice::Iter<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+#[derive(Default)]
+pub struct MinMaxStack<T> {
+    stack: Vec<(T, T, T)>, // .0 value .1 min .2 max
+}
+
+impl<T: Clone + Ord> MinMaxStack<T> {
     pub fn new() -> Self {
-        MinStack { stack: vec![] }
+        MinMaxStack { stack: vec![] }
     }
 
     pub fn push(&mut self, val: T) {
-        if self.is_empty() {
-            self.stack.push((val.clone(), val));
-        } else {
-            let min = self.min().unwrap().to_owned();
-            self.stack.push((val.clone(), val.min(min)));
+        match self.stack.last() {
+            None => self.stack.push((val.clone(), val.clone(), val)),
+            Some((_, min, max)) => {
+                let (min, max) = (val.clone().min(min.clone()), val.clone().max(max.clone()));
+                self.stack.push((val, min, max));
+            }
         }
     }
 
@@ -31,6 +169,10 @@ impl<T: Clone + Ord> MinStack<T> {
         self.stack.last().map(|x| &x.1)
     }
 
+    pub fn max(&self) -> Option<&T> {
+        self.stack.last().map(|x| &x.2)
+    }
+
     pub fn is_empty(&self) -> bool {
         self.stack.is_empty()
     }
@@ -40,17 +182,129 @@ impl<T: Clone + Ord> MinStack<T> {
     }
 }
 
-#[derive(Default)]
+impl<T: Clone + Ord + Sub<Output = T>> MinMaxStack<T> {
+    pub fn range(&self) -> Option<T> {
+        self.stack.last().map(|x| x.2.clone() - x.1.clone())
+    }
+}
+
+#[derive(Clone)]
 pub struct MinQueue<T> {
-    old: MinStack<T>,
-    new: MinStack<T>,
+    queue: SlidingAggregator<T, Min, T>,
+}
+
+impl<T: Clone + Ord> Default for MinQueue<T> {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl<T: Clone + Ord> MinQueue<T> {
     pub fn new() -> Self {
         MinQueue {
-            old: MinStack::new(),
-            new: MinStack::new(),
+            queue: SlidingAggregator::new(),
+        }
+    }
+
+    pub fn push(&mut self, val: T) {
+        self.queue.push(val);
+    }
+
+    pub fn pop(&mut self) -> Option<T> {
+        self.queue.pop()
+    }
+
+    pub fn front(&self) -> Option<&T> {
+        self.queue.front()
+    }
+
+    pub fn back(&self) -> Option<&T> {
+        self.queue.back()
+    }
+
+    // Values from the oldest to the newest.
+    pub fn iter(&self) -> Iter<'_, T, T> {
+        self.queue.iter()
+    }
+
+    pub fn min(&self) -> Option<&T> {
+        match self.queue.parts() {
+            (None, None) => None,
+            (Some(x), None) | (None, Some(x)) => Some(x),
+            (Some(x), Some(y)) => Some(x.min(y)),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.queue.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.queue.is_empty()
+    }
+}
+
+impl<T: Clone + Ord + Debug> Debug for MinQueue<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+// Queues are equal when they hold the same values in the same order, however
+// the values are split between the stacks.
+impl<T: Clone + Ord> PartialEq for MinQueue<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.len() == other.len() && self.iter().eq(other.iter())
+    }
+}
+
+impl<T: Clone + Ord> Eq for MinQueue<T> {}
+
+impl<T: Clone + Ord> Extend<T> for MinQueue<T> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for val in iter {
+            self.push(val);
+        }
+    }
+}
+
+impl<T: Clone + Ord> FromIterator<T> for MinQueue<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut res = Self::new();
+        res.extend(iter);
+        res
+    }
+}
+
+impl<T: Clone + Ord> IntoIterator for MinQueue<T> {
+    type Item = T;
+    type IntoIter = IntoValues<T, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.queue.into_values()
+    }
+}
+
+impl<'a, T: Clone + Ord> IntoIterator for &'a MinQueue<T> {
+    type Item = &'a T;
+    type IntoIter = Iter<'a, T, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+#[derive(Default)]
+pub struct MinMaxQueue<T> {
+    old: MinMaxStack<T>,
+    new: MinMaxStack<T>,
+}
+
+impl<T: Clone + Ord> MinMaxQueue<T> {
+    pub fn new() -> Self {
+        MinMaxQueue {
+            old: MinMaxStack::new(),
+            new: MinMaxStack::new(),
         }
     }
 
@@ -61,38 +315,39 @@ impl<T: Clone + Ord> MinQueue<T> {
     pub fn pop(&mut self) -> Option<T> {
         if self.old.is_empty() {
             self.new_to_old();
-            self.old.pop()
-        } else {
-            self.old.pop()
         }
+        self.old.pop()
     }
 
     fn new_to_old(&mut self) {
-        while !self.new.is_empty() {
-            self.old.push(self.new.pop().unwrap());
+        while let Some(val) = self.new.pop() {
+            self.old.push(val);
         }
     }
 
-    pub fn front(&mut self) -> Option<&T> {
-        if self.old.is_empty() {
-            self.new_to_old();
-            self.old.peek()
-        } else {
-            self.old.peek()
-        }
+    // With `old` empty, the front is the bottom of `new`.
+    pub fn front(&self) -> Option<&T> {
+        self.old
+            .peek()
+            .or_else(|| self.new.stack.first().map(|x| &x.0))
     }
 
     pub fn min(&self) -> Option<&T> {
-        let m1 = self.new.min();
-        let m2 = self.old.min();
-        match (m1, m2) {
+        match (self.new.min(), self.old.min()) {
             (None, None) => None,
-            (Some(x), None) => Some(x),
-            (None, Some(x)) => Some(x),
+            (Some(x), None) | (None, Some(x)) => Some(x),
             (Some(x), Some(y)) => Some(x.min(y)),
         }
     }
 
+    pub fn max(&self) -> Option<&T> {
+        match (self.new.max(), self.old.max()) {
+            (None, None) => None,
+            (Some(x), None) | (None, Some(x)) => Some(x),
+            (Some(x), Some(y)) => Some(x.max(y)),
+        }
+    }
+
     pub fn len(&self) -> usize {
         self.new.len() + self.old.len()
     }
@@ -102,9 +357,100 @@ impl<T: Clone + Ord> MinQueue<T> {
     }
 }
 
+impl<T: Clone + Ord + Sub<Output = T>> MinMaxQueue<T> {
+    pub fn range(&self) -> Option<T> {
+        Some(self.max()?.clone() - self.min()?.clone())
+    }
+}
+
+pub struct MinDeque<T> {
+    front: MinStack<T>,
+    back: MinStack<T>,
+}
+
+impl<T: Ord> Default for MinDeque<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Ord> MinDeque<T> {
+    pub fn new() -> Self {
+        MinDeque {
+            front: MinStack::new(),
+            back: MinStack::new(),
+        }
+    }
+
+    pub fn push_front(&mut self, val: T) {
+        self.front.push(val);
+    }
+
+    pub fn push_back(&mut self, val: T) {
+        self.back.push(val);
+    }
+
+    pub fn pop_front(&mut self) -> Option<T> {
+        if self.front.is_empty() {
+            Self::split(&mut self.back, &mut self.front);
+        }
+        self.front.pop()
+    }
+
+    pub fn pop_back(&mut self) -> Option<T> {
+        if self.back.is_empty() {
+            Self::split(&mut self.front, &mut self.back);
+        }
+        self.back.pop()
+    }
+
+    // With one half empty, the end is the bottom of the other half.
+    pub fn front(&self) -> Option<&T> {
+        self.front.peek().or_else(|| self.back.iter().next())
+    }
+
+    pub fn back(&self) -> Option<&T> {
+        self.back.peek().or_else(|| self.front.iter().next())
+    }
+
+    // Moves the bottom half of `from` (rounded up) to the empty `to`, so
+    // that both ends stay cheap to pop: every element moved by a split needs
+    // the other half to be popped before it can be moved again.
+    fn split(from: &mut MinStack<T>, to: &mut MinStack<T>) {
+        let mut items = Vec::with_capacity(from.len());
+        while let Some(val) = from.pop() {
+            items.push(val);
+        }
+        let keep = items.len() / 2;
+        let moved = items.split_off(keep);
+        for val in items.into_iter().rev() {
+            from.push(val);
+        }
+        for val in moved {
+            to.push(val);
+        }
+    }
+
+    pub fn min(&self) -> Option<&T> {
+        match (self.front.min(), self.back.min()) {
+            (None, None) => None,
+            (Some(x), None) | (None, Some(x)) => Some(x),
+            (Some(x), Some(y)) => Some(x.min(y)),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.front.len() + self.back.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.front.is_empty() && self.back.is_empty()
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use super::MinQueue;
+    use super::{MinDeque, MinMaxQueue, MinMaxStack, MinQueue, MinStack};
     use ntest::timeout;
     use rand::Rng;
     use std::collections::VecDeque;
@@ -136,6 +482,10 @@ mod tests {
             self.data.iter().min()
         }
 
+        pub fn max(&self) -> Option<&T> {
+            self.data.iter().max()
+        }
+
         pub fn len(&self) -> usize {
             self.data.len()
         }
@@ -145,6 +495,95 @@ mod tests {
         }
     }
 
+    #[test]
+    fn read_only_access() {
+        let mut queue: MinQueue<i32> = [5, 1, 4].into_iter().collect();
+        queue.pop();
+        queue.extend([3, 2]);
+        let shared = &queue;
+        assert_eq!(shared.front(), Some(&1));
+        assert_eq!(shared.back(), Some(&2));
+        assert_eq!(shared.iter().collect::<Vec<_>>(), vec![&1, &4, &3, &2]);
+        assert_eq!(format!("{:?}", shared), "[1, 4, 3, 2]");
+
+        let same: MinQueue<i32> = [1, 4, 3, 2].into_iter().collect();
+        assert_eq!(queue, same);
+        let copy = queue.clone();
+        queue.pop();
+        assert_ne!(queue, copy);
+        assert_eq!(copy.into_iter().collect::<Vec<_>>(), vec![1, 4, 3, 2]);
+        let mut sum = 0;
+        for value in &queue {
+            sum += value;
+        }
+        assert_eq!(sum, 9);
+
+        let mut stack: MinStack<i32> = [3, 1, 2].into_iter().collect();
+        stack.extend([7]);
+        assert_eq!(format!("{:?}", stack), "[3, 1, 2, 7]");
+        assert_eq!((&stack).into_iter().max(), Some(&7));
+        let copy = stack.clone();
+        assert_eq!(stack.pop(), Some(7));
+        assert_ne!(stack, copy);
+        assert_eq!(copy.into_iter().collect::<Vec<_>>(), vec![3, 1, 2, 7]);
+
+        let mut min_max = MinMaxQueue::new();
+        min_max.push(6);
+        min_max.push(8);
+        let shared = &min_max;
+        assert_eq!(shared.front(), Some(&6));
+        assert_eq!(min_max.pop(), Some(6));
+        min_max.push(2);
+        let shared = &min_max;
+        assert_eq!(shared.front(), Some(&8));
+        assert_eq!(shared.range(), Some(6));
+    }
+
+    #[test]
+    fn stack_without_clone() {
+        #[derive(Debug, PartialEq, Eq, PartialOrd, Ord)]
+        struct Id(u32);
+
+        let mut stack = MinStack::new();
+        for value in [5, 3, 3, 7, 1, 1] {
+            stack.push(Id(value));
+        }
+        assert_eq!(stack.mins.len(), 3);
+        let mut mins = vec![];
+        while stack.pop().is_some() {
+            mins.push(stack.min().map(|min| min.0));
+        }
+        assert_eq!(
+            mins,
+            vec![Some(1), Some(3), Some(3), Some(3), Some(5), None]
+        );
+
+        let mut deque = MinDeque::new();
+        deque.push_back(Id(2));
+        deque.push_front(Id(1));
+        assert_eq!(deque.min().map(|min| min.0), Some(1));
+        assert_eq!(deque.pop_back().map(|id| id.0), Some(2));
+    }
+
+    #[test]
+    fn stack_compare_with_naive() {
+        let mut stack = MinStack::new();
+        let mut naive = vec![];
+        let mut rng = rand::thread_rng();
+        for _ in 0..10000 {
+            if rng.gen_range(0..3) == 0 {
+                assert_eq!(stack.pop(), naive.pop());
+            } else {
+                let value = rng.gen_range(0..50);
+                stack.push(value);
+                naive.push(value);
+            }
+            assert_eq!(stack.min(), naive.iter().min());
+            assert_eq!(stack.peek(), naive.last());
+            assert_eq!(stack.len(), naive.len());
+        }
+    }
+
     #[test]
     fn it_works() {
         let mut queue = MinQueue::new();
@@ -246,6 +685,124 @@ mod tests {
         }
     }
 
+    #[test]
+    fn back_and_iter_compare_with_naive() {
+        let mut queue = MinQueue::new();
+        let mut naive = NaiveMinQueue::new();
+        let mut rng = rand::thread_rng();
+        for _ in 0..10000 {
+            if rng.gen_range(0..3) == 0 {
+                assert_eq!(queue.pop(), naive.pop());
+            } else {
+                let value = rng.gen::<u64>();
+                queue.push(value);
+                naive.push(value);
+            }
+            assert_eq!(queue.back(), naive.data.back());
+            assert!(queue.iter().eq(naive.data.iter()));
+        }
+    }
+
+    #[test]
+    fn min_max_stack() {
+        let mut stack = MinMaxStack::new();
+        assert_eq!(stack.range(), None);
+        for (value, min, max) in [(3, 3, 3), (5, 3, 5), (1, 1, 5), (4, 1, 5)] {
+            stack.push(value);
+            assert_eq!(stack.peek(), Some(&value));
+            assert_eq!(stack.min(), Some(&min));
+            assert_eq!(stack.max(), Some(&max));
+            assert_eq!(stack.range(), Some(max - min));
+        }
+        assert_eq!(stack.len(), 4);
+        assert_eq!(stack.pop(), Some(4));
+        assert_eq!(stack.pop(), Some(1));
+        assert_eq!(stack.min(), Some(&3));
+        assert_eq!(stack.range(), Some(2));
+    }
+
+    #[test]
+    fn min_max_compare_with_naive() {
+        let mut queue = MinMaxQueue::new();
+        let mut naive = NaiveMinQueue::new();
+        let mut rng = rand::thread_rng();
+        for _ in 0..10000 {
+            if rng.gen_range(0..3) == 0 {
+                assert_eq!(queue.pop(), naive.pop());
+            } else {
+                let value = rng.gen_range(-1000i64..1000);
+                queue.push(value);
+                naive.push(value);
+            }
+            assert_eq!(queue.len(), naive.len());
+            assert_eq!(queue.is_empty(), naive.is_empty());
+            assert_eq!(queue.front(), naive.front());
+            assert_eq!(queue.min(), naive.min());
+            assert_eq!(queue.max(), naive.max());
+            assert_eq!(
+                queue.range(),
+                naive.min().map(|min| naive.max().unwrap() - min)
+            );
+        }
+    }
+
+    #[test]
+    fn deque_compare_with_naive() {
+        let mut deque = MinDeque::new();
+        let mut naive = VecDeque::new();
+        let mut rng = rand::thread_rng();
+        for _ in 0..20000 {
+            match rng.gen_range(0..6) {
+                0 => assert_eq!(deque.pop_front(), naive.pop_front()),
+                1 => assert_eq!(deque.pop_back(), naive.pop_back()),
+                2 | 3 => {
+                    let value = rng.gen_range(0..1000);
+                    deque.push_front(value);
+                    naive.push_front(value);
+                }
+                _ => {
+                    let value = rng.gen_range(0..1000);
+                    deque.push_back(value);
+                    naive.push_back(value);
+                }
+            }
+            assert_eq!(deque.len(), naive.len());
+            assert_eq!(deque.is_empty(), naive.is_empty());
+            assert_eq!(deque.min(), naive.iter().min());
+            if rng.gen() {
+                assert_eq!(deque.front(), naive.front());
+            } else {
+                assert_eq!(deque.back(), naive.back());
+            }
+        }
+    }
+
+    #[test]
+    #[timeout(2000)]
+    fn deque_alternating_ends() {
+        let mut deque = MinDeque::new();
+        for i in 0..200000 {
+            deque.push_back(i);
+        }
+        for i in 0..100000 {
+            assert_eq!(deque.min(), Some(&i));
+            assert_eq!(deque.pop_front(), Some(i));
+            assert_eq!(deque.pop_back(), Some(199999 - i));
+        }
+        assert!(deque.is_empty());
+
+        deque.push_back(1);
+        deque.push_back(2);
+        let shared = &deque;
+        assert_eq!(shared.front(), Some(&1));
+        assert_eq!(shared.back(), Some(&2));
+        deque.push_front(0);
+        assert_eq!(deque.pop_back(), Some(2));
+        let shared = &deque;
+        assert_eq!(shared.front(), Some(&0));
+        assert_eq!(shared.back(), Some(&1));
+    }
+
     #[test]
     #[timeout(2000)]
     fn stress() {
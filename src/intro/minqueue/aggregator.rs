@@ -0,0 +1,346 @@
+#![forbid(unsafe_code)]
+
+use std::iter::{Chain, Map, Rev};
+use std::ops::Add;
+use std::{slice, vec};
+
+////////////////////////////////////////////////////////////////////////////////
+
+// `combine` must be associative, but not necessarily commutative: the
+// aggregate of a window is `lift(a1) ⊕ lift(a2) ⊕ ... ⊕ lift(an)` in FIFO order.
+pub trait Associative<T> {
+    type Output: Clone;
+
+    fn lift(&self, value: &T) -> Self::Output;
+    fn combine(&self, left: &Self::Output, right: &Self::Output) -> Self::Output;
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Sum;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Min;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Max;
+
+// (min, max) of the window.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct MinMax;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Gcd;
+
+impl<T: Clone + Add<Output = T>> Associative<T> for Sum {
+    type Output = T;
+
+    fn lift(&self, value: &T) -> T {
+        value.clone()
+    }
+
+    fn combine(&self, left: &T, right: &T) -> T {
+        left.clone() + right.clone()
+    }
+}
+
+impl<T: Clone + Ord> Associative<T> for Min {
+    type Output = T;
+
+    fn lift(&self, value: &T) -> T {
+        value.clone()
+    }
+
+    fn combine(&self, left: &T, right: &T) -> T {
+        left.min(right).clone()
+    }
+}
+
+impl<T: Clone + Ord> Associative<T> for Max {
+    type Output = T;
+
+    fn lift(&self, value: &T) -> T {
+        value.clone()
+    }
+
+    fn combine(&self, left: &T, right: &T) -> T {
+        left.max(right).clone()
+    }
+}
+
+impl<T: Clone + Ord> Associative<T> for MinMax {
+    type Output = (T, T);
+
+    fn lift(&self, value: &T) -> (T, T) {
+        (value.clone(), value.clone())
+    }
+
+    fn combine(&self, left: &(T, T), right: &(T, T)) -> (T, T) {
+        (
+            left.0.clone().min(right.0.clone()),
+            left.1.clone().max(right.1.clone()),
+        )
+    }
+}
+
+macro_rules! impl_gcd {
+    ($($t:ty),*) => {$(
+        impl Associative<$t> for Gcd {
+            type Output = $t;
+
+            fn lift(&self, value: &$t) -> $t {
+                *value
+            }
+
+            fn combine(&self, left: &$t, right: &$t) -> $t {
+                let (mut a, mut b) = (*left, *right);
+                while b != 0 {
+                    (a, b) = (b, a % b);
+                }
+                a
+            }
+        }
+    )*};
+}
+
+impl_gcd!(u8, u16, u32, u64, u128, usize);
+
+////////////////////////////////////////////////////////////////////////////////
+
+// The two-stack queue: values are pushed onto `back` and popped from `front`,
+// each element keeping the aggregate of itself and everything below it.
+// `front` aggregates towards newer elements (`value ⊕ below`), `back` towards
+// older ones (`below ⊕ value`), so the whole window is `front ⊕ back`.
+// `A` is always `Op::Output`, spelled out so that the definition itself needs
+// no bounds.
+#[derive(Clone)]
+pub struct SlidingAggregator<T, Op, A = <Op as Associative<T>>::Output> {
+    op: Op,
+    front: Vec<(T, A)>,
+    back: Vec<(T, A)>,
+}
+
+pub type Iter<'a, T, A> =
+    Map<Chain<Rev<slice::Iter<'a, (T, A)>>, slice::Iter<'a, (T, A)>>, fn(&'a (T, A)) -> &'a T>;
+
+pub type IntoValues<T, A> =
+    Map<Chain<Rev<vec::IntoIter<(T, A)>>, vec::IntoIter<(T, A)>>, fn((T, A)) -> T>;
+
+fn value<T, A>(entry: &(T, A)) -> &T {
+    &entry.0
+}
+
+fn into_value<T, A>(entry: (T, A)) -> T {
+    entry.0
+}
+
+impl<T, Op: Associative<T> + Default> Default for SlidingAggregator<T, Op> {
+    fn default() -> Self {
+        Self::with_op(Op::default())
+    }
+}
+
+impl<T, Op: Associative<T> + Default> SlidingAggregator<T, Op> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl<T, Op: Associative<T>> SlidingAggregator<T, Op> {
+    pub fn with_op(op: Op) -> Self {
+        SlidingAggregator {
+            op,
+            front: vec![],
+            back: vec![],
+        }
+    }
+
+    pub fn op(&self) -> &Op {
+        &self.op
+    }
+
+    pub fn push(&mut self, val: T) {
+        let lifted = self.op.lift(&val);
+        let aggregate = match self.back.last() {
+            Some((_, below)) => self.op.combine(below, &lifted),
+            None => lifted,
+        };
+        self.back.push((val, aggregate));
+    }
+
+    pub fn pop(&mut self) -> Option<T> {
+        if self.front.is_empty() {
+            self.back_to_front();
+        }
+        self.front.pop().map(|x| x.0)
+    }
+
+    fn back_to_front(&mut self) {
+        while let Some((val, _)) = self.back.pop() {
+            let lifted = self.op.lift(&val);
+            let aggregate = match self.front.last() {
+                Some((_, below)) => self.op.combine(&lifted, below),
+                None => lifted,
+            };
+            self.front.push((val, aggregate));
+        }
+    }
+
+    // Until the next transfer the oldest element may still be at the bottom
+    // of `back`, and the newest one at the bottom of `front`.
+    pub fn front(&self) -> Option<&T> {
+        self.front.last().or(self.back.first()).map(|x| &x.0)
+    }
+
+    pub fn back(&self) -> Option<&T> {
+        self.back.last().or(self.front.first()).map(|x| &x.0)
+    }
+
+    // Values from the oldest to the newest.
+    pub fn iter(&self) -> Iter<'_, T, Op::Output> {
+        self.front
+            .iter()
+            .rev()
+            .chain(self.back.iter())
+            .map(value as fn(&(T, Op::Output)) -> &T)
+    }
+
+    pub fn into_values(self) -> IntoValues<T, Op::Output> {
+        self.front
+            .into_iter()
+            .rev()
+            .chain(self.back)
+            .map(into_value as fn((T, Op::Output)) -> T)
+    }
+
+    // Aggregates of the older and the newer part of the window; the whole
+    // window is their combination.
+    pub(super) fn parts(&self) -> (Option<&Op::Output>, Option<&Op::Output>) {
+        (
+            self.front.last().map(|x| &x.1),
+            self.back.last().map(|x| &x.1),
+        )
+    }
+
+    pub fn aggregate(&self) -> Option<Op::Output> {
+        match self.parts() {
+            (None, None) => None,
+            (Some(x), None) | (None, Some(x)) => Some(x.clone()),
+            (Some(x), Some(y)) => Some(self.op.combine(x, y)),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.front.len() + self.back.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.front.is_empty() && self.back.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Associative, Gcd, Max, MinMax, SlidingAggregator, Sum};
+    use rand::Rng;
+    use std::collections::VecDeque;
+
+    // 2x2 matrices modulo a prime: associative, but not commutative.
+    struct MatMul;
+
+    type Matrix = [[u64; 2]; 2];
+
+    const MOD: u64 = 1_000_000_007;
+
+    impl Associative<Matrix> for MatMul {
+        type Output = Matrix;
+
+        fn lift(&self, value: &Matrix) -> Matrix {
+            *value
+        }
+
+        fn combine(&self, a: &Matrix, b: &Matrix) -> Matrix {
+            let mut res = [[0; 2]; 2];
+            for (i, row) in res.iter_mut().enumerate() {
+                for (j, cell) in row.iter_mut().enumerate() {
+                    *cell = (a[i][0] * b[0][j] + a[i][1] * b[1][j]) % MOD;
+                }
+            }
+            res
+        }
+    }
+
+    fn compare<T, Op>(op: Op, mut gen: impl FnMut() -> T)
+    where
+        T: Clone + PartialEq + std::fmt::Debug,
+        Op: Associative<T>,
+        Op::Output: PartialEq + std::fmt::Debug,
+    {
+        let mut queue = SlidingAggregator::with_op(op);
+        let mut naive = VecDeque::new();
+        let mut rng = rand::thread_rng();
+        for _ in 0..3000 {
+            if rng.gen_range(0..3) == 0 {
+                assert_eq!(queue.pop(), naive.pop_front());
+            } else {
+                let value = gen();
+                queue.push(value.clone());
+                naive.push_back(value);
+            }
+            let op = queue.op();
+            let expected = naive
+                .iter()
+                .map(|value| op.lift(value))
+                .reduce(|acc, value| op.combine(&acc, &value));
+            assert_eq!(queue.aggregate(), expected);
+            assert_eq!(queue.len(), naive.len());
+            assert_eq!(queue.front(), naive.front());
+            assert_eq!(queue.back(), naive.back());
+            assert!(queue.iter().eq(naive.iter()));
+        }
+        assert!(queue.into_values().eq(naive.into_iter()));
+    }
+
+    #[test]
+    fn operations() {
+        let mut rng = rand::thread_rng();
+        compare(Sum, || rng.gen_range(-100i64..100));
+        let mut rng = rand::thread_rng();
+        compare(Max, || rng.gen::<u32>());
+        let mut rng = rand::thread_rng();
+        compare(MinMax, || rng.gen_range(0..1000));
+        let mut rng = rand::thread_rng();
+        compare(Gcd, || 6 * rng.gen_range(1u64..50));
+        let mut rng = rand::thread_rng();
+        compare(MatMul, || {
+            [
+                [rng.gen_range(0..MOD), rng.gen_range(0..MOD)],
+                [rng.gen_range(0..MOD), rng.gen_range(0..MOD)],
+            ]
+        });
+    }
+
+    #[test]
+    fn window() {
+        let mut queue = SlidingAggregator::<u64, Gcd>::new();
+        assert!(queue.is_empty());
+        assert_eq!(queue.aggregate(), None);
+        for value in [12, 18, 8] {
+            queue.push(value);
+        }
+        assert_eq!(queue.aggregate(), Some(2));
+        queue.pop();
+        queue.pop();
+        queue.push(24);
+        assert_eq!(queue.aggregate(), Some(8));
+        assert_eq!(queue.len(), 2);
+
+        let mut spread = SlidingAggregator::<i32, MinMax>::new();
+        for value in [5, -3, 7] {
+            spread.push(value);
+        }
+        assert_eq!(spread.aggregate(), Some((-3, 7)));
+        spread.pop();
+        spread.pop();
+        assert_eq!(spread.aggregate(), Some((7, 7)));
+    }
+}
@@ -0,0 +1,206 @@
+#![forbid(unsafe_code)]
+
+////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OverflowPolicy {
+    // `push` into a full queue gives the value back.
+    Reject,
+    // `push` into a full queue pops the oldest element first.
+    EvictOldest,
+}
+
+// Both the values and the monotonic deque of candidate minima are ring
+// buffers allocated in `new`, so the queue never allocates afterwards.
+// The front element lives in slot `start` and the following ones in the
+// slots after it, wrapping around.
+pub struct BoundedMinQueue<T> {
+    values: Vec<Option<T>>,
+    // Slot of the front element and the number of elements.
+    start: usize,
+    len: usize,
+    // Slots of the elements that are smaller than everything pushed after
+    // them, oldest first: `mins[(mins_start + i) % capacity]`.
+    mins: Vec<usize>,
+    mins_start: usize,
+    mins_len: usize,
+    policy: OverflowPolicy,
+}
+
+impl<T: Ord> BoundedMinQueue<T> {
+    pub fn new(capacity: usize, policy: OverflowPolicy) -> Self {
+        if capacity == 0 {
+            panic!("Неверная вместимость");
+        }
+        BoundedMinQueue {
+            values: (0..capacity).map(|_| None).collect(),
+            start: 0,
+            len: 0,
+            mins: vec![0; capacity],
+            mins_start: 0,
+            mins_len: 0,
+            policy,
+        }
+    }
+
+    // Returns the evicted element, if any, or the value itself when it was
+    // rejected.
+    pub fn push(&mut self, val: T) -> Result<Option<T>, T> {
+        let evicted = if self.is_full() {
+            match self.policy {
+                OverflowPolicy::Reject => return Err(val),
+                OverflowPolicy::EvictOldest => self.pop(),
+            }
+        } else {
+            None
+        };
+        let capacity = self.capacity();
+        while self.mins_len > 0 {
+            let back = self.mins[(self.mins_start + self.mins_len - 1) % capacity];
+            if self.values[back].as_ref().unwrap() <= &val {
+                break;
+            }
+            self.mins_len -= 1;
+        }
+        let slot = (self.start + self.len) % capacity;
+        self.values[slot] = Some(val);
+        self.mins[(self.mins_start + self.mins_len) % capacity] = slot;
+        self.mins_len += 1;
+        self.len += 1;
+        Ok(evicted)
+    }
+
+    pub fn pop(&mut self) -> Option<T> {
+        if self.len == 0 {
+            return None;
+        }
+        let capacity = self.capacity();
+        if self.mins[self.mins_start] == self.start {
+            self.mins_start = (self.mins_start + 1) % capacity;
+            self.mins_len -= 1;
+        }
+        let val = self.values[self.start].take();
+        self.start = (self.start + 1) % capacity;
+        self.len -= 1;
+        val
+    }
+
+    pub fn front(&self) -> Option<&T> {
+        self.get(self.start)
+    }
+
+    pub fn back(&self) -> Option<&T> {
+        self.get((self.start + self.len + self.capacity() - 1) % self.capacity())
+    }
+
+    pub fn min(&self) -> Option<&T> {
+        self.get(self.mins[self.mins_start])
+    }
+
+    fn get(&self, slot: usize) -> Option<&T> {
+        if self.len == 0 {
+            return None;
+        }
+        self.values[slot].as_ref()
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.values.len()
+    }
+
+    pub fn policy(&self) -> OverflowPolicy {
+        self.policy
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.len == self.capacity()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{BoundedMinQueue, OverflowPolicy};
+    use rand::Rng;
+    use std::collections::VecDeque;
+
+    #[test]
+    fn it_works() {
+        let mut queue = BoundedMinQueue::new(3, OverflowPolicy::Reject);
+        assert_eq!(queue.min(), None);
+        assert_eq!(queue.front(), None);
+        assert_eq!(queue.back(), None);
+        assert_eq!(queue.push(4), Ok(None));
+        assert_eq!(queue.push(2), Ok(None));
+        assert_eq!(queue.push(3), Ok(None));
+        assert!(queue.is_full());
+        assert_eq!(queue.push(1), Err(1));
+        assert_eq!(queue.min(), Some(&2));
+        assert_eq!(queue.pop(), Some(4));
+        assert_eq!(queue.pop(), Some(2));
+        assert_eq!(queue.min(), Some(&3));
+        assert_eq!(queue.push(5), Ok(None));
+        assert_eq!(queue.front(), Some(&3));
+        assert_eq!(queue.back(), Some(&5));
+
+        let mut queue = BoundedMinQueue::new(2, OverflowPolicy::EvictOldest);
+        assert_eq!(queue.policy(), OverflowPolicy::EvictOldest);
+        assert_eq!(queue.push(1), Ok(None));
+        assert_eq!(queue.push(7), Ok(None));
+        assert_eq!(queue.push(6), Ok(Some(1)));
+        assert_eq!(queue.min(), Some(&6));
+        assert_eq!(queue.push(9), Ok(Some(7)));
+        assert_eq!(queue.len(), 2);
+        assert_eq!(queue.min(), Some(&6));
+    }
+
+    #[test]
+    fn values_need_not_be_clone() {
+        let mut queue = BoundedMinQueue::new(2, OverflowPolicy::EvictOldest);
+        queue.push(Box::new(3)).unwrap();
+        queue.push(Box::new(2)).unwrap();
+        assert_eq!(queue.push(Box::new(5)), Ok(Some(Box::new(3))));
+        assert_eq!(queue.min(), Some(&Box::new(2)));
+    }
+
+    #[test]
+    fn compare_with_naive() {
+        let mut rng = rand::thread_rng();
+        for policy in [OverflowPolicy::Reject, OverflowPolicy::EvictOldest] {
+            let capacity = 17;
+            let mut queue = BoundedMinQueue::new(capacity, policy);
+            let mut naive = VecDeque::new();
+            for _ in 0..20000 {
+                if rng.gen_range(0..3) == 0 {
+                    assert_eq!(queue.pop(), naive.pop_front());
+                } else {
+                    let value = rng.gen_range(0..100);
+                    let expected = if naive.len() < capacity {
+                        naive.push_back(value);
+                        Ok(None)
+                    } else if policy == OverflowPolicy::Reject {
+                        Err(value)
+                    } else {
+                        naive.push_back(value);
+                        Ok(naive.pop_front())
+                    };
+                    assert_eq!(queue.push(value), expected);
+                }
+                assert_eq!(queue.len(), naive.len());
+                assert_eq!(queue.min(), naive.iter().min());
+                assert_eq!(queue.front(), naive.front());
+                assert_eq!(queue.back(), naive.back());
+                assert!(queue.start < capacity);
+            }
+            assert_eq!(queue.values.capacity(), capacity);
+            assert_eq!(queue.mins.capacity(), capacity);
+        }
+    }
+}
@@ -0,0 +1,33 @@
+#![forbid(unsafe_code)]
+
+use std::cell::Cell;
+
+////////////////////////////////////////////////////////////////////////////////
+
+thread_local! {
+    static WORK: Cell<usize> = const { Cell::new(0) };
+}
+
+// Counts clones and drops, which is the work the queues do per element moved
+// or thrown away.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Counted(pub u64);
+
+impl Clone for Counted {
+    fn clone(&self) -> Self {
+        WORK.with(|work| work.set(work.get() + 1));
+        Counted(self.0)
+    }
+}
+
+impl Drop for Counted {
+    fn drop(&mut self) {
+        WORK.with(|work| work.set(work.get() + 1));
+    }
+}
+
+pub fn work<R>(f: impl FnOnce() -> R) -> usize {
+    let before = WORK.with(Cell::get);
+    f();
+    WORK.with(Cell::get) - before
+}
@@ -0,0 +1,268 @@
+#![forbid(unsafe_code)]
+
+use std::fmt::{self, Debug, Formatter};
+use std::rc::Rc;
+
+////////////////////////////////////////////////////////////////////////////////
+
+// An immutable list, newest first. Every node knows the minimum of itself and
+// everything after it.
+struct List<T>(Option<Rc<Node<T>>>);
+
+struct Node<T> {
+    value: T,
+    min: T,
+    next: List<T>,
+}
+
+impl<T> Clone for List<T> {
+    fn clone(&self) -> Self {
+        List(self.0.clone())
+    }
+}
+
+impl<T: Clone + Ord> List<T> {
+    fn cons(&self, value: T) -> Self {
+        let min = match self.min() {
+            Some(min) if *min < value => min.clone(),
+            _ => value.clone(),
+        };
+        List(Some(Rc::new(Node {
+            value,
+            min,
+            next: self.clone(),
+        })))
+    }
+
+    fn reversed(&self) -> Self {
+        self.iter()
+            .fold(List(None), |res, value| res.cons(value.clone()))
+    }
+}
+
+impl<T> List<T> {
+    fn min(&self) -> Option<&T> {
+        self.0.as_ref().map(|node| &node.min)
+    }
+
+    fn iter(&self) -> impl Iterator<Item = &T> {
+        let mut node = self.0.as_deref();
+        std::iter::from_fn(move || {
+            let current = node?;
+            node = current.next.0.as_deref();
+            Some(&current.value)
+        })
+    }
+}
+
+// Dropping a long chain recursively would overflow the call stack.
+impl<T> Drop for List<T> {
+    fn drop(&mut self) {
+        let mut head = self.0.take();
+        while let Some(node) = head {
+            match Rc::try_unwrap(node) {
+                Ok(mut node) => head = node.next.0.take(),
+                Err(_) => break,
+            }
+        }
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+// The two-list queue: values are pushed onto `back` and popped from `front`,
+// and `back` is reversed into `front` once `front` runs out, so `front` is
+// empty only in the empty queue. The lists are immutable, so every version
+// stays valid and shares its nodes with the others. Push and pop are O(1)
+// amortized as long as every version is popped at most once; popping the same
+// version with one value left in `front` again redoes the reversal each time.
+pub struct PersistentMinQueue<T> {
+    front: List<T>,
+    back: List<T>,
+    len: usize,
+}
+
+impl<T> Clone for PersistentMinQueue<T> {
+    fn clone(&self) -> Self {
+        PersistentMinQueue {
+            front: self.front.clone(),
+            back: self.back.clone(),
+            len: self.len,
+        }
+    }
+}
+
+impl<T: Clone + Ord> Default for PersistentMinQueue<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Clone + Ord> PersistentMinQueue<T> {
+    pub fn new() -> Self {
+        PersistentMinQueue {
+            front: List(None),
+            back: List(None),
+            len: 0,
+        }
+    }
+
+    pub fn push(&self, val: T) -> Self {
+        if self.is_empty() {
+            return PersistentMinQueue {
+                front: self.front.cons(val),
+                back: List(None),
+                len: 1,
+            };
+        }
+        PersistentMinQueue {
+            front: self.front.clone(),
+            back: self.back.cons(val),
+            len: self.len + 1,
+        }
+    }
+
+    // The front value and the queue without it.
+    pub fn pop(&self) -> Option<(T, Self)> {
+        let node = self.front.0.as_deref()?;
+        let queue = match node.next.0 {
+            Some(_) => PersistentMinQueue {
+                front: node.next.clone(),
+                back: self.back.clone(),
+                len: self.len - 1,
+            },
+            None => PersistentMinQueue {
+                front: self.back.reversed(),
+                back: List(None),
+                len: self.len - 1,
+            },
+        };
+        Some((node.value.clone(), queue))
+    }
+
+    pub fn front(&self) -> Option<&T> {
+        self.front.0.as_ref().map(|node| &node.value)
+    }
+
+    pub fn min(&self) -> Option<&T> {
+        match (self.front.min(), self.back.min()) {
+            (None, None) => None,
+            (Some(x), None) | (None, Some(x)) => Some(x),
+            (Some(x), Some(y)) => Some(x.min(y)),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    // Values from the oldest to the newest.
+    pub fn iter(&self) -> impl Iterator<Item = &T> {
+        let back: Vec<_> = self.back.iter().collect();
+        self.front.iter().chain(back.into_iter().rev())
+    }
+}
+
+impl<T: Clone + Ord + Debug> Debug for PersistentMinQueue<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+impl<T: Clone + Ord> FromIterator<T> for PersistentMinQueue<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        iter.into_iter()
+            .fold(Self::new(), |queue, val| queue.push(val))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::PersistentMinQueue;
+    use crate::intro::minqueue::counted::{work, Counted};
+    use rand::Rng;
+    use std::collections::VecDeque;
+
+    #[test]
+    fn it_works() {
+        let empty = PersistentMinQueue::new();
+        let one = empty.push(3);
+        let two = one.push(1);
+        let three = two.push(2);
+        assert_eq!(empty.min(), None);
+        assert_eq!(one.min(), Some(&3));
+        assert_eq!(three.min(), Some(&1));
+
+        let (val, popped) = three.pop().unwrap();
+        assert_eq!(val, 3);
+        assert_eq!(popped.front(), Some(&1));
+        let (val, popped) = popped.pop().unwrap();
+        assert_eq!(val, 1);
+        assert_eq!(popped.min(), Some(&2));
+        assert_eq!(popped.len(), 1);
+
+        assert_eq!(three.iter().collect::<Vec<_>>(), [&3, &1, &2]);
+        assert_eq!(format!("{:?}", two), "[3, 1]");
+        assert!(empty.pop().is_none());
+    }
+
+    #[test]
+    fn old_versions_stay_valid() {
+        let mut versions = vec![(PersistentMinQueue::new(), VecDeque::new())];
+        let mut rng = rand::thread_rng();
+        for _ in 0..3000 {
+            let (queue, naive) = versions[rng.gen_range(0..versions.len())].clone();
+            let (queue, naive) = if rng.gen_range(0..3) == 0 {
+                let mut naive = naive;
+                match queue.pop() {
+                    Some((val, queue)) => {
+                        assert_eq!(Some(val), naive.pop_front());
+                        (queue, naive)
+                    }
+                    None => (queue, naive),
+                }
+            } else {
+                let value = rng.gen_range(0..1000);
+                let mut naive = naive;
+                naive.push_back(value);
+                (queue.push(value), naive)
+            };
+            versions.push((queue, naive));
+        }
+        for (queue, naive) in &versions {
+            assert_eq!(queue.len(), naive.len());
+            assert_eq!(queue.front(), naive.front());
+            assert_eq!(queue.min(), naive.iter().min());
+            assert!(queue.iter().eq(naive.iter()));
+        }
+    }
+
+    #[test]
+    fn amortized_work() {
+        const OPS: usize = 30000;
+        let mut queue = PersistentMinQueue::new();
+        let mut rng = rand::thread_rng();
+        let total = work(|| {
+            for i in 0..OPS {
+                if i % 3 == 2 {
+                    queue = queue.pop().unwrap().1;
+                } else {
+                    queue = queue.push(Counted(rng.gen()));
+                }
+            }
+        });
+        assert!(total <= 8 * OPS, "{}", total);
+    }
+
+    #[test]
+    fn long_chain() {
+        let queue: PersistentMinQueue<_> = (0..1000000).collect();
+        let (_, queue) = queue.pop().unwrap();
+        assert_eq!(queue.min(), Some(&1));
+        assert_eq!(queue.iter().count(), 999999);
+    }
+}
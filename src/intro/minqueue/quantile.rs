@@ -0,0 +1,309 @@
+#![forbid(unsafe_code)]
+
+use std::cmp::Ordering;
+use std::collections::VecDeque;
+
+////////////////////////////////////////////////////////////////////////////////
+
+const NIL: usize = usize::MAX;
+
+struct Node<T> {
+    value: Option<T>,
+    // Push number, which makes keys unique among equal values.
+    seq: usize,
+    priority: u64,
+    left: usize,
+    right: usize,
+    size: usize,
+}
+
+// A FIFO queue with order statistics: the values also live in a treap keyed
+// by `(value, seq)` with subtree sizes, so any rank is a walk down the tree.
+// Nodes are kept in an arena and the FIFO order is a queue of node indices.
+pub struct QuantileQueue<T> {
+    nodes: Vec<Node<T>>,
+    free: Vec<usize>,
+    root: usize,
+    order: VecDeque<usize>,
+    seq: usize,
+    rng: u64,
+}
+
+impl<T: Ord> Default for QuantileQueue<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Ord> QuantileQueue<T> {
+    pub fn new() -> Self {
+        QuantileQueue {
+            nodes: vec![],
+            free: vec![],
+            root: NIL,
+            order: VecDeque::new(),
+            seq: 0,
+            rng: 0x2545_f491_4f6c_dd1d,
+        }
+    }
+
+    pub fn push(&mut self, val: T) {
+        let node = Node {
+            value: Some(val),
+            seq: self.seq,
+            priority: self.random(),
+            left: NIL,
+            right: NIL,
+            size: 1,
+        };
+        self.seq += 1;
+        let id = match self.free.pop() {
+            Some(id) => {
+                self.nodes[id] = node;
+                id
+            }
+            None => {
+                self.nodes.push(node);
+                self.nodes.len() - 1
+            }
+        };
+        let (left, right) = self.split(self.root, id, false);
+        let left = self.merge(left, id);
+        self.root = self.merge(left, right);
+        self.order.push_back(id);
+    }
+
+    pub fn pop(&mut self) -> Option<T> {
+        let id = self.order.pop_front()?;
+        let (left, right) = self.split(self.root, id, false);
+        let (node, right) = self.split(right, id, true);
+        debug_assert_eq!(node, id);
+        self.root = self.merge(left, right);
+        self.free.push(id);
+        self.nodes[id].value.take()
+    }
+
+    pub fn front(&self) -> Option<&T> {
+        self.order.front().map(|&id| self.value(id))
+    }
+
+    // The `k`-th smallest value, counting from zero.
+    pub fn kth(&self, mut k: usize) -> Option<&T> {
+        if k >= self.len() {
+            return None;
+        }
+        let mut node = self.root;
+        loop {
+            let left = self.size(self.nodes[node].left);
+            match k.cmp(&left) {
+                Ordering::Less => node = self.nodes[node].left,
+                Ordering::Equal => return Some(self.value(node)),
+                Ordering::Greater => {
+                    k -= left + 1;
+                    node = self.nodes[node].right;
+                }
+            }
+        }
+    }
+
+    // The lower median for an even number of values.
+    pub fn median(&self) -> Option<&T> {
+        self.quantile(0.5)
+    }
+
+    // The value at index `floor(q * (len - 1))` in sorted order, so 0 gives
+    // the minimum and 1 the maximum.
+    pub fn quantile(&self, q: f64) -> Option<&T> {
+        if !(0.0..=1.0).contains(&q) {
+            panic!("Неверный квантиль");
+        }
+        let last = self.len().checked_sub(1)?;
+        self.kth(((last as f64 * q).floor() as usize).min(last))
+    }
+
+    // The number of values less than `x`.
+    pub fn rank(&self, x: &T) -> usize {
+        let mut res = 0;
+        let mut node = self.root;
+        while node != NIL {
+            if self.value(node) < x {
+                res += self.size(self.nodes[node].left) + 1;
+                node = self.nodes[node].right;
+            } else {
+                node = self.nodes[node].left;
+            }
+        }
+        res
+    }
+
+    pub fn len(&self) -> usize {
+        self.order.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.order.is_empty()
+    }
+
+    fn value(&self, node: usize) -> &T {
+        self.nodes[node].value.as_ref().unwrap()
+    }
+
+    fn size(&self, node: usize) -> usize {
+        if node == NIL {
+            0
+        } else {
+            self.nodes[node].size
+        }
+    }
+
+    fn update(&mut self, node: usize) {
+        let Node { left, right, .. } = self.nodes[node];
+        self.nodes[node].size = self.size(left) + self.size(right) + 1;
+    }
+
+    fn key_less(&self, a: usize, b: usize) -> bool {
+        (self.value(a), self.nodes[a].seq) < (self.value(b), self.nodes[b].seq)
+    }
+
+    // Splits off the nodes with keys less than the key of `pivot` (or not
+    // greater, when `inclusive`) into the left tree.
+    fn split(&mut self, node: usize, pivot: usize, inclusive: bool) -> (usize, usize) {
+        if node == NIL {
+            return (NIL, NIL);
+        }
+        let goes_left = if inclusive {
+            !self.key_less(pivot, node)
+        } else {
+            self.key_less(node, pivot)
+        };
+        if goes_left {
+            let (left, right) = self.split(self.nodes[node].right, pivot, inclusive);
+            self.nodes[node].right = left;
+            self.update(node);
+            (node, right)
+        } else {
+            let (left, right) = self.split(self.nodes[node].left, pivot, inclusive);
+            self.nodes[node].left = right;
+            self.update(node);
+            (left, node)
+        }
+    }
+
+    // Every key of `left` is less than every key of `right`.
+    fn merge(&mut self, left: usize, right: usize) -> usize {
+        if left == NIL {
+            return right;
+        }
+        if right == NIL {
+            return left;
+        }
+        if self.nodes[left].priority > self.nodes[right].priority {
+            let merged = self.merge(self.nodes[left].right, right);
+            self.nodes[left].right = merged;
+            self.update(left);
+            left
+        } else {
+            let merged = self.merge(left, self.nodes[right].left);
+            self.nodes[right].left = merged;
+            self.update(right);
+            right
+        }
+    }
+
+    // xorshift64
+    fn random(&mut self) -> u64 {
+        self.rng ^= self.rng << 13;
+        self.rng ^= self.rng >> 7;
+        self.rng ^= self.rng << 17;
+        self.rng
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::QuantileQueue;
+    use rand::Rng;
+    use std::collections::VecDeque;
+
+    #[test]
+    fn it_works() {
+        let mut queue = QuantileQueue::new();
+        assert_eq!(queue.median(), None);
+        assert_eq!(queue.rank(&5), 0);
+        for value in [5, 1, 4, 1, 3] {
+            queue.push(value);
+        }
+        assert_eq!(queue.median(), Some(&3));
+        assert_eq!(queue.quantile(0.0), Some(&1));
+        assert_eq!(queue.quantile(1.0), Some(&5));
+        assert_eq!(queue.quantile(0.3), Some(&1));
+        assert_eq!(queue.rank(&4), 3);
+        assert_eq!(queue.rank(&1), 0);
+        assert_eq!(queue.kth(2), Some(&3));
+        assert_eq!(queue.kth(5), None);
+
+        assert_eq!(queue.pop(), Some(5));
+        assert_eq!(queue.pop(), Some(1));
+        assert_eq!(queue.front(), Some(&4));
+        assert_eq!(queue.median(), Some(&3));
+        assert_eq!(queue.quantile(1.0), Some(&4));
+        assert_eq!(queue.len(), 3);
+    }
+
+    #[test]
+    #[should_panic]
+    fn bad_quantile() {
+        let mut queue = QuantileQueue::new();
+        queue.push(1);
+        queue.quantile(1.5);
+    }
+
+    #[test]
+    fn compare_with_sorted() {
+        let mut queue = QuantileQueue::new();
+        let mut naive = VecDeque::new();
+        let mut rng = rand::thread_rng();
+        for _ in 0..5000 {
+            if rng.gen_range(0..3) == 0 {
+                assert_eq!(queue.pop(), naive.pop_front());
+            } else {
+                let value = rng.gen_range(0..100);
+                queue.push(value);
+                naive.push_back(value);
+            }
+            let mut sorted: Vec<_> = naive.iter().copied().collect();
+            sorted.sort();
+            assert_eq!(queue.len(), sorted.len());
+            assert_eq!(queue.front(), naive.front());
+            let q = rng.gen_range(0.0..=1.0);
+            if sorted.is_empty() {
+                assert_eq!(queue.median(), None);
+                assert_eq!(queue.quantile(q), None);
+            } else {
+                let last = sorted.len() - 1;
+                assert_eq!(queue.median(), Some(&sorted[last / 2]));
+                let index = (last as f64 * q).floor() as usize;
+                assert_eq!(queue.quantile(q), Some(&sorted[index]));
+                let k = rng.gen_range(0..sorted.len());
+                assert_eq!(queue.kth(k), Some(&sorted[k]));
+            }
+            let x = rng.gen_range(0..110);
+            assert_eq!(queue.rank(&x), sorted.partition_point(|&v| v < x));
+        }
+        assert!(queue.nodes.len() <= 5000);
+    }
+
+    #[test]
+    fn long_window() {
+        let mut queue = QuantileQueue::new();
+        for i in 0..200000u32 {
+            queue.push(i.wrapping_mul(2654435761) % 1000);
+            if queue.len() > 1000 {
+                queue.pop();
+            }
+        }
+        assert_eq!(queue.len(), 1000);
+        assert_eq!(queue.nodes.len(), 1001);
+        assert!(queue.median().is_some());
+    }
+}
@@ -0,0 +1,548 @@
+#![forbid(unsafe_code)]
+
+use super::aggregator::{Associative, Gcd, Max, Min, MinMax, Sum};
+use std::ops::{Add, Range};
+
+////////////////////////////////////////////////////////////////////////////////
+
+// `x ⊕ x = x`, so a range may be covered by overlapping parts.
+pub trait Idempotent<T>: Associative<T> {}
+
+impl<T: Clone + Ord> Idempotent<T> for Min {}
+impl<T: Clone + Ord> Idempotent<T> for Max {}
+impl<T: Clone + Ord> Idempotent<T> for MinMax {}
+
+macro_rules! impl_idempotent_gcd {
+    ($($t:ty),*) => {$(
+        impl Idempotent<$t> for Gcd {}
+    )*};
+}
+
+impl_idempotent_gcd!(u8, u16, u32, u64, u128, usize);
+
+// Adding `delta` to each of `len` values turns their aggregate into
+// `apply(aggregate, delta, len)`.
+pub trait RangeAdd<T>: Associative<T> {
+    fn apply(&self, aggregate: &Self::Output, delta: &T, len: usize) -> Self::Output;
+}
+
+impl<T: Clone + Add<Output = T>> RangeAdd<T> for Sum {
+    fn apply(&self, aggregate: &T, delta: &T, len: usize) -> T {
+        aggregate.clone() + times(delta, len)
+    }
+}
+
+impl<T: Clone + Ord + Add<Output = T>> RangeAdd<T> for Min {
+    fn apply(&self, aggregate: &T, delta: &T, _len: usize) -> T {
+        aggregate.clone() + delta.clone()
+    }
+}
+
+impl<T: Clone + Ord + Add<Output = T>> RangeAdd<T> for Max {
+    fn apply(&self, aggregate: &T, delta: &T, _len: usize) -> T {
+        aggregate.clone() + delta.clone()
+    }
+}
+
+impl<T: Clone + Ord + Add<Output = T>> RangeAdd<T> for MinMax {
+    fn apply(&self, aggregate: &(T, T), delta: &T, _len: usize) -> (T, T) {
+        (
+            aggregate.0.clone() + delta.clone(),
+            aggregate.1.clone() + delta.clone(),
+        )
+    }
+}
+
+// `value` added to itself `n > 0` times, by doubling.
+fn times<T: Clone + Add<Output = T>>(value: &T, n: usize) -> T {
+    let mut res: Option<T> = None;
+    let mut power = value.clone();
+    let mut n = n;
+    loop {
+        if n & 1 == 1 {
+            res = Some(match res {
+                Some(res) => res + power.clone(),
+                None => power.clone(),
+            });
+        }
+        n >>= 1;
+        if n == 0 {
+            break;
+        }
+        power = power.clone() + power;
+    }
+    res.unwrap()
+}
+
+fn check(range: &Range<usize>, len: usize) {
+    if range.start > range.end || range.end > len {
+        panic!("Неверный диапазон");
+    }
+}
+
+fn check_index(index: usize, len: usize) {
+    if index >= len {
+        panic!("Неверный индекс");
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+// `table[k][i]` is the aggregate of `[i, i + 2^k)`; any range is covered by
+// two such blocks.
+pub struct SparseTable<T, Op: Associative<T>> {
+    op: Op,
+    table: Vec<Vec<Op::Output>>,
+}
+
+impl<T, Op: Idempotent<T> + Default> SparseTable<T, Op> {
+    pub fn new(values: &[T]) -> Self {
+        Self::with_op(values, Op::default())
+    }
+}
+
+impl<T, Op: Idempotent<T>> SparseTable<T, Op> {
+    pub fn with_op(values: &[T], op: Op) -> Self {
+        let mut table: Vec<Vec<Op::Output>> = vec![values.iter().map(|x| op.lift(x)).collect()];
+        let mut width = 1;
+        while 2 * width <= values.len() {
+            let prev = table.last().unwrap();
+            let row = (0..=values.len() - 2 * width)
+                .map(|i| op.combine(&prev[i], &prev[i + width]))
+                .collect();
+            table.push(row);
+            width *= 2;
+        }
+        SparseTable { op, table }
+    }
+
+    pub fn len(&self) -> usize {
+        self.table[0].len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.table[0].is_empty()
+    }
+
+    // `None` for an empty range.
+    pub fn query(&self, range: Range<usize>) -> Option<Op::Output> {
+        check(&range, self.len());
+        if range.is_empty() {
+            return None;
+        }
+        let level = (range.len()).ilog2() as usize;
+        let row = &self.table[level];
+        Some(
+            self.op
+                .combine(&row[range.start], &row[range.end - (1 << level)]),
+        )
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+// Bottom-up tree over `size` leaves (a power of two), `tree[1]` being the
+// root; unused leaves are `None`.
+pub struct SegmentTree<T, Op: Associative<T>> {
+    op: Op,
+    len: usize,
+    size: usize,
+    tree: Vec<Option<Op::Output>>,
+    values: Vec<T>,
+}
+
+impl<T, Op: Associative<T> + Default> SegmentTree<T, Op> {
+    pub fn new(values: Vec<T>) -> Self {
+        Self::with_op(values, Op::default())
+    }
+}
+
+impl<T, Op: Associative<T>> SegmentTree<T, Op> {
+    pub fn with_op(values: Vec<T>, op: Op) -> Self {
+        let size = values.len().next_power_of_two();
+        let mut tree = vec![None; 2 * size];
+        for (i, value) in values.iter().enumerate() {
+            tree[size + i] = Some(op.lift(value));
+        }
+        let mut res = SegmentTree {
+            op,
+            len: values.len(),
+            size,
+            tree,
+            values,
+        };
+        for node in (1..size).rev() {
+            res.pull(node);
+        }
+        res
+    }
+
+    fn pull(&mut self, node: usize) {
+        self.tree[node] = combine(&self.op, &self.tree[2 * node], &self.tree[2 * node + 1]);
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn get(&self, index: usize) -> &T {
+        check_index(index, self.len);
+        &self.values[index]
+    }
+
+    pub fn set(&mut self, index: usize, value: T) {
+        check_index(index, self.len);
+        let mut node = self.size + index;
+        self.tree[node] = Some(self.op.lift(&value));
+        self.values[index] = value;
+        while node > 1 {
+            node /= 2;
+            self.pull(node);
+        }
+    }
+
+    pub fn query(&self, range: Range<usize>) -> Option<Op::Output> {
+        check(&range, self.len);
+        let (mut left, mut right) = (None, None);
+        let (mut l, mut r) = (range.start + self.size, range.end + self.size);
+        while l < r {
+            if l & 1 == 1 {
+                left = combine(&self.op, &left, &self.tree[l]);
+                l += 1;
+            }
+            if r & 1 == 1 {
+                r -= 1;
+                right = combine(&self.op, &self.tree[r], &right);
+            }
+            l /= 2;
+            r /= 2;
+        }
+        combine(&self.op, &left, &right)
+    }
+}
+
+fn combine<T, Op: Associative<T>>(
+    op: &Op,
+    left: &Option<Op::Output>,
+    right: &Option<Op::Output>,
+) -> Option<Op::Output> {
+    match (left, right) {
+        (None, None) => None,
+        (Some(x), None) | (None, Some(x)) => Some(x.clone()),
+        (Some(x), Some(y)) => Some(op.combine(x, y)),
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+// Top-down tree with pending additions: `tree[node]` already includes
+// `lazy[node]`, which still has to be passed to the children.
+pub struct LazySegmentTree<T, Op: RangeAdd<T>> {
+    op: Op,
+    len: usize,
+    tree: Vec<Option<Op::Output>>,
+    lazy: Vec<Option<T>>,
+}
+
+impl<T: Clone + Add<Output = T>, Op: RangeAdd<T> + Default> LazySegmentTree<T, Op> {
+    pub fn new(values: &[T]) -> Self {
+        Self::with_op(values, Op::default())
+    }
+}
+
+impl<T: Clone + Add<Output = T>, Op: RangeAdd<T>> LazySegmentTree<T, Op> {
+    pub fn with_op(values: &[T], op: Op) -> Self {
+        let mut res = LazySegmentTree {
+            op,
+            len: values.len(),
+            tree: vec![None; 4 * values.len().max(1)],
+            lazy: vec![None; 4 * values.len().max(1)],
+        };
+        if !values.is_empty() {
+            res.build(1, 0..values.len(), values);
+        }
+        res
+    }
+
+    fn build(&mut self, node: usize, span: Range<usize>, values: &[T]) {
+        if span.len() == 1 {
+            self.tree[node] = Some(self.op.lift(&values[span.start]));
+            return;
+        }
+        let mid = (span.start + span.end) / 2;
+        self.build(2 * node, span.start..mid, values);
+        self.build(2 * node + 1, mid..span.end, values);
+        self.pull(node);
+    }
+
+    fn pull(&mut self, node: usize) {
+        self.tree[node] = combine(&self.op, &self.tree[2 * node], &self.tree[2 * node + 1]);
+    }
+
+    fn apply(&mut self, node: usize, delta: &T, len: usize) {
+        if let Some(aggregate) = &self.tree[node] {
+            self.tree[node] = Some(self.op.apply(aggregate, delta, len));
+        }
+        self.lazy[node] = Some(match self.lazy[node].take() {
+            Some(pending) => pending + delta.clone(),
+            None => delta.clone(),
+        });
+    }
+
+    fn push(&mut self, node: usize, span: &Range<usize>) {
+        if let Some(delta) = self.lazy[node].take() {
+            let mid = (span.start + span.end) / 2;
+            self.apply(2 * node, &delta, mid - span.start);
+            self.apply(2 * node + 1, &delta, span.end - mid);
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn set(&mut self, index: usize, value: T) {
+        check_index(index, self.len);
+        self.set_in(1, 0..self.len, index, &value);
+    }
+
+    fn set_in(&mut self, node: usize, span: Range<usize>, index: usize, value: &T) {
+        if span.len() == 1 {
+            self.tree[node] = Some(self.op.lift(value));
+            self.lazy[node] = None;
+            return;
+        }
+        self.push(node, &span);
+        let mid = (span.start + span.end) / 2;
+        if index < mid {
+            self.set_in(2 * node, span.start..mid, index, value);
+        } else {
+            self.set_in(2 * node + 1, mid..span.end, index, value);
+        }
+        self.pull(node);
+    }
+
+    // Adds `delta` to every value in `range`.
+    pub fn add(&mut self, range: Range<usize>, delta: T) {
+        check(&range, self.len);
+        if !range.is_empty() {
+            self.add_in(1, 0..self.len, &range, &delta);
+        }
+    }
+
+    fn add_in(&mut self, node: usize, span: Range<usize>, range: &Range<usize>, delta: &T) {
+        if range.end <= span.start || span.end <= range.start {
+            return;
+        }
+        if range.start <= span.start && span.end <= range.end {
+            self.apply(node, delta, span.len());
+            return;
+        }
+        self.push(node, &span);
+        let mid = (span.start + span.end) / 2;
+        self.add_in(2 * node, span.start..mid, range, delta);
+        self.add_in(2 * node + 1, mid..span.end, range, delta);
+        self.pull(node);
+    }
+
+    pub fn query(&self, range: Range<usize>) -> Option<Op::Output> {
+        check(&range, self.len);
+        if range.is_empty() {
+            return None;
+        }
+        self.query_in(1, 0..self.len, &range, None)
+    }
+
+    // Nothing is pushed down here: `pending` is the sum of the additions
+    // still held by the ancestors of `node`.
+    fn query_in(
+        &self,
+        node: usize,
+        span: Range<usize>,
+        range: &Range<usize>,
+        pending: Option<&T>,
+    ) -> Option<Op::Output> {
+        if range.end <= span.start || span.end <= range.start {
+            return None;
+        }
+        if range.start <= span.start && span.end <= range.end {
+            let aggregate = self.tree[node].as_ref()?;
+            return Some(match pending {
+                Some(delta) => self.op.apply(aggregate, delta, span.len()),
+                None => aggregate.clone(),
+            });
+        }
+        let pending = match (pending, &self.lazy[node]) {
+            (Some(x), Some(y)) => Some(x.clone() + y.clone()),
+            (x, y) => x.or(y.as_ref()).cloned(),
+        };
+        let mid = (span.start + span.end) / 2;
+        let left = self.query_in(2 * node, span.start..mid, range, pending.as_ref());
+        let right = self.query_in(2 * node + 1, mid..span.end, range, pending.as_ref());
+        combine(&self.op, &left, &right)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{times, LazySegmentTree, SegmentTree, SparseTable};
+    use crate::intro::minqueue::aggregator::{Associative, Gcd, Max, Min, MinMax, Sum};
+    use rand::Rng;
+
+    fn naive<T, Op: Associative<T>>(op: &Op, values: &[T]) -> Option<Op::Output> {
+        values
+            .iter()
+            .map(|x| op.lift(x))
+            .reduce(|acc, x| op.combine(&acc, &x))
+    }
+
+    fn random_range(rng: &mut impl Rng, len: usize) -> std::ops::Range<usize> {
+        let start = rng.gen_range(0..=len);
+        start..rng.gen_range(start..=len)
+    }
+
+    #[test]
+    fn doubling() {
+        assert_eq!(times(&3, 1), 3);
+        assert_eq!(times(&3, 10), 30);
+        assert_eq!(times(&0.5, 7), 3.5);
+    }
+
+    #[test]
+    fn sparse_table() {
+        let values = [5, 2, 8, 6, 3, 7];
+        let table = SparseTable::<i32, Min>::new(&values);
+        assert_eq!(table.query(0..6), Some(2));
+        assert_eq!(table.query(2..5), Some(3));
+        assert_eq!(table.query(3..3), None);
+        assert_eq!(
+            SparseTable::<i32, MinMax>::new(&values).query(2..6),
+            Some((3, 8))
+        );
+
+        let mut rng = rand::thread_rng();
+        for len in 0..40 {
+            let values: Vec<u64> = (0..len).map(|_| 6 * rng.gen_range(1..30)).collect();
+            let max = SparseTable::<u64, Max>::new(&values);
+            let gcd = SparseTable::<u64, Gcd>::new(&values);
+            assert_eq!(max.len(), len);
+            for _ in 0..50 {
+                let range = random_range(&mut rng, len);
+                assert_eq!(
+                    max.query(range.clone()),
+                    naive(&Max, &values[range.clone()])
+                );
+                assert_eq!(gcd.query(range.clone()), naive(&Gcd, &values[range]));
+            }
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn sparse_table_out_of_range() {
+        SparseTable::<i32, Min>::new(&[1, 2, 3]).query(1..4);
+    }
+
+    #[test]
+    fn segment_tree() {
+        let mut rng = rand::thread_rng();
+        for len in [0, 1, 2, 7, 16, 33] {
+            let mut values: Vec<u64> = (0..len).map(|_| rng.gen_range(1..100)).collect();
+            let mut gcd = SegmentTree::<u64, Gcd>::new(values.clone());
+            let mut sum = SegmentTree::<u64, Sum>::new(values.clone());
+            for _ in 0..300 {
+                if len > 0 && rng.gen() {
+                    let index = rng.gen_range(0..len);
+                    values[index] = rng.gen_range(1..100);
+                    gcd.set(index, values[index]);
+                    sum.set(index, values[index]);
+                    assert_eq!(*sum.get(index), values[index]);
+                }
+                let range = random_range(&mut rng, len);
+                assert_eq!(
+                    gcd.query(range.clone()),
+                    naive(&Gcd, &values[range.clone()])
+                );
+                assert_eq!(sum.query(range.clone()), naive(&Sum, &values[range]));
+            }
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn segment_tree_out_of_range() {
+        let mut tree = SegmentTree::<u64, Sum>::new(vec![1, 2, 3]);
+        tree.set(3, 5);
+    }
+
+    #[test]
+    #[should_panic(expected = "Неверный индекс")]
+    fn segment_tree_get_out_of_range() {
+        SegmentTree::<u64, Sum>::new(vec![1, 2, 3]).get(3);
+    }
+
+    #[test]
+    fn lazy_query_is_read_only() {
+        let mut tree = LazySegmentTree::<i64, Sum>::new(&[1, 2, 3, 4, 5]);
+        tree.add(0..5, 10);
+        tree.add(1..3, -1);
+        let shared = &tree;
+        assert_eq!(shared.query(0..5), Some(63));
+        assert_eq!(shared.query(2..4), Some(26));
+        assert_eq!(shared.query(4..5), Some(15));
+    }
+
+    #[test]
+    fn lazy_segment_tree() {
+        let mut rng = rand::thread_rng();
+        for len in [1, 2, 5, 16, 37] {
+            let mut values: Vec<i64> = (0..len).map(|_| rng.gen_range(-50..50)).collect();
+            let mut sum = LazySegmentTree::<i64, Sum>::new(&values);
+            let mut min = LazySegmentTree::<i64, Min>::new(&values);
+            let mut spread = LazySegmentTree::<i64, MinMax>::new(&values);
+            for _ in 0..500 {
+                match rng.gen_range(0..3) {
+                    0 => {
+                        let index = rng.gen_range(0..len);
+                        values[index] = rng.gen_range(-50..50);
+                        sum.set(index, values[index]);
+                        min.set(index, values[index]);
+                        spread.set(index, values[index]);
+                    }
+                    1 => {
+                        let range = random_range(&mut rng, len);
+                        let delta = rng.gen_range(-10..10);
+                        for value in &mut values[range.clone()] {
+                            *value += delta;
+                        }
+                        sum.add(range.clone(), delta);
+                        min.add(range.clone(), delta);
+                        spread.add(range, delta);
+                    }
+                    _ => {}
+                }
+                let range = random_range(&mut rng, len);
+                assert_eq!(
+                    sum.query(range.clone()),
+                    naive(&Sum, &values[range.clone()])
+                );
+                assert_eq!(
+                    min.query(range.clone()),
+                    naive(&Min, &values[range.clone()])
+                );
+                assert_eq!(spread.query(range.clone()), naive(&MinMax, &values[range]));
+            }
+        }
+        let empty = LazySegmentTree::<i64, Sum>::new(&[]);
+        assert!(empty.is_empty());
+        assert_eq!(empty.query(0..0), None);
+    }
+}
@@ -0,0 +1,245 @@
+#![forbid(unsafe_code)]
+
+use std::collections::VecDeque;
+
+////////////////////////////////////////////////////////////////////////////////
+
+// Rebuild steps done by every operation. The rebuild starts when the back
+// part outgrows the front one, so there are at most `2 * front + 1` minima to
+// compute, and the front lasts at least `front` more pops: three steps per
+// operation finish it in time.
+const STEPS: usize = 3;
+
+// Elements are numbered in push order. The front part `[head, front_end)`
+// keeps the minima of its suffixes, the back part only the running minimum.
+// Instead of moving the back into the front at once when the front runs out,
+// the minima of all suffixes of `[head, end)` are computed a few at a time in
+// advance, while the old front keeps serving pops. The work per operation is
+// bounded only while the queue fits into the capacity it was created with:
+// past it the buffers are reallocated, which takes time proportional to the
+// length.
+pub struct RealTimeMinQueue<T> {
+    items: VecDeque<T>,
+    head: usize,
+    front_end: usize,
+    // Minimum of `[i, front_end)` for `i` from `front_end - 1` down to `head`.
+    front: Vec<T>,
+    back_min: Option<T>,
+    rebuild: Option<Rebuild<T>>,
+    // The buffer of the previous front, emptied a few minima at a time and
+    // reused by the next rebuild.
+    spare: Vec<T>,
+}
+
+struct Rebuild<T> {
+    end: usize,
+    // Minima of `[i, end)` are known for `i >= next`.
+    next: usize,
+    suffix: Vec<T>,
+    // Minimum of `[front_end, end)`, the back part when the rebuild started.
+    frozen: Option<T>,
+}
+
+impl<T: Clone + Ord> Default for RealTimeMinQueue<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Clone + Ord> RealTimeMinQueue<T> {
+    pub fn new() -> Self {
+        Self::with_capacity(0)
+    }
+
+    pub fn with_capacity(capacity: usize) -> Self {
+        RealTimeMinQueue {
+            items: VecDeque::with_capacity(capacity),
+            head: 0,
+            front_end: 0,
+            front: Vec::with_capacity(capacity),
+            back_min: None,
+            rebuild: None,
+            spare: Vec::with_capacity(capacity),
+        }
+    }
+
+    pub fn push(&mut self, val: T) {
+        if self.back_min.as_ref().is_none_or(|min| val < *min) {
+            self.back_min = Some(val.clone());
+        }
+        self.items.push_back(val);
+        self.tick();
+    }
+
+    pub fn pop(&mut self) -> Option<T> {
+        if self.head == self.front_end && !self.items.is_empty() {
+            // Never happens while the rebuild keeps up, but finishing it
+            // here keeps the queue correct regardless.
+            self.finish_rebuild();
+        }
+        let val = self.items.pop_front()?;
+        self.front.pop();
+        self.head += 1;
+        if let Some(rebuild) = &mut self.rebuild {
+            // The minimum of the suffix that starts at the popped element.
+            if rebuild.next < self.head {
+                rebuild.next = self.head;
+                rebuild.suffix.pop();
+            }
+        }
+        self.tick();
+        Some(val)
+    }
+
+    fn tick(&mut self) {
+        let back = self.head + self.items.len() - self.front_end;
+        if self.rebuild.is_none() && back > self.front_end - self.head {
+            self.start_rebuild();
+        }
+        for _ in 0..STEPS {
+            self.step();
+            self.spare.pop();
+        }
+    }
+
+    fn start_rebuild(&mut self) {
+        if self.rebuild.is_some() {
+            return;
+        }
+        // Already empty: the spare buffer is emptied faster than the back
+        // part outgrows the front one.
+        self.spare.clear();
+        let end = self.head + self.items.len();
+        self.rebuild = Some(Rebuild {
+            end,
+            next: end,
+            suffix: std::mem::take(&mut self.spare),
+            frozen: self.back_min.take(),
+        });
+    }
+
+    fn step(&mut self) {
+        let Some(rebuild) = &mut self.rebuild else {
+            return;
+        };
+        if rebuild.next == self.head {
+            let rebuild = self.rebuild.take().unwrap();
+            self.spare = std::mem::replace(&mut self.front, rebuild.suffix);
+            self.front_end = rebuild.end;
+            return;
+        }
+        rebuild.next -= 1;
+        let item = &self.items[rebuild.next - self.head];
+        let min = match rebuild.suffix.last() {
+            Some(min) if min < item => min.clone(),
+            _ => item.clone(),
+        };
+        rebuild.suffix.push(min);
+    }
+
+    fn finish_rebuild(&mut self) {
+        self.start_rebuild();
+        while self.rebuild.is_some() {
+            self.step();
+        }
+    }
+
+    pub fn front(&self) -> Option<&T> {
+        self.items.front()
+    }
+
+    pub fn back(&self) -> Option<&T> {
+        self.items.back()
+    }
+
+    pub fn min(&self) -> Option<&T> {
+        let frozen = self.rebuild.as_ref().and_then(|r| r.frozen.as_ref());
+        [self.front.last(), frozen, self.back_min.as_ref()]
+            .into_iter()
+            .flatten()
+            .min()
+    }
+
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{RealTimeMinQueue, STEPS};
+    use crate::intro::minqueue::counted::{work, Counted};
+    use crate::intro::minqueue::MinQueue;
+    use rand::Rng;
+    use std::collections::VecDeque;
+
+    #[test]
+    fn compare_with_naive() {
+        let mut queue = RealTimeMinQueue::new();
+        let mut naive = VecDeque::new();
+        let mut rng = rand::thread_rng();
+        for _ in 0..30000 {
+            if rng.gen_range(0..3) == 0 {
+                assert_eq!(queue.pop(), naive.pop_front());
+            } else {
+                let value = rng.gen_range(0..1000);
+                queue.push(value);
+                naive.push_back(value);
+            }
+            assert_eq!(queue.len(), naive.len());
+            assert_eq!(queue.is_empty(), naive.is_empty());
+            assert_eq!(queue.front(), naive.front());
+            assert_eq!(queue.back(), naive.back());
+            assert_eq!(queue.min(), naive.iter().min());
+        }
+    }
+
+    // Capacities of the buffers: any reallocation changes them.
+    fn capacities<T>(queue: &RealTimeMinQueue<T>) -> [usize; 3] {
+        let other = match &queue.rebuild {
+            Some(rebuild) => rebuild.suffix.capacity(),
+            None => queue.spare.capacity(),
+        };
+        let (front, other) = (queue.front.capacity(), other);
+        [queue.items.capacity(), front.min(other), front.max(other)]
+    }
+
+    #[test]
+    fn bounded_work_per_operation() {
+        let mut amortized = MinQueue::new();
+        let mut realtime = RealTimeMinQueue::with_capacity(150000);
+        let initial = capacities(&realtime);
+        let (mut amortized_max, mut realtime_max) = (0, 0);
+        let mut rng = rand::thread_rng();
+        for round in 0..4 {
+            for _ in 0..5000 << round {
+                let value = rng.gen();
+                amortized_max = amortized_max.max(work(|| amortized.push(Counted(value))));
+                realtime_max = realtime_max.max(work(|| realtime.push(Counted(value))));
+                assert_eq!(capacities(&realtime), initial);
+            }
+            for _ in 0..5000 << round {
+                if rng.gen() {
+                    let value = rng.gen();
+                    amortized_max = amortized_max.max(work(|| amortized.push(Counted(value))));
+                    realtime_max = realtime_max.max(work(|| realtime.push(Counted(value))));
+                }
+                let mut expected = None;
+                amortized_max = amortized_max.max(work(|| expected = amortized.pop()));
+                let mut popped = None;
+                realtime_max = realtime_max.max(work(|| popped = realtime.pop()));
+                assert_eq!(popped, expected);
+                assert_eq!(realtime.min(), amortized.min());
+                assert_eq!(capacities(&realtime), initial);
+            }
+        }
+        // Every step clones at most one minimum and drops at most one from the
+        // spare buffer; a push or a pop adds a couple more.
+        assert!(realtime_max <= 2 * STEPS + 2, "{}", realtime_max);
+        assert!(amortized_max >= 5000, "{}", amortized_max);
+    }
+}
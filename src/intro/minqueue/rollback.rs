@@ -0,0 +1,476 @@
+#![forbid(unsafe_code)]
+
+use super::MinStack;
+use std::collections::VecDeque;
+
+////////////////////////////////////////////////////////////////////////////////
+
+// Returned by `MinStack::checkpoint`, consumed by `rollback` or `release`.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Checkpoint {
+    level: usize,
+    journal_len: usize,
+}
+
+#[derive(Clone)]
+enum Undo<T> {
+    Push,
+    Pop(T),
+}
+
+// The undo journal of a `MinStack`. Popped values are journaled while a
+// checkpoint is open, so the caller gets a copy.
+#[derive(Clone)]
+pub(super) struct Journal<T> {
+    // Journal lengths at the open checkpoints, oldest first.
+    checkpoints: Vec<usize>,
+    undo: Vec<Undo<T>>,
+    // `T::clone`, saved by `checkpoint`: popping only needs `T: Ord`.
+    copy: Option<fn(&T) -> T>,
+}
+
+impl<T> Default for Journal<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> Journal<T> {
+    pub(super) fn new() -> Self {
+        Journal {
+            checkpoints: vec![],
+            undo: vec![],
+            copy: None,
+        }
+    }
+
+    pub(super) fn pushed(&mut self) {
+        if !self.checkpoints.is_empty() {
+            self.undo.push(Undo::Push);
+        }
+    }
+
+    pub(super) fn popped(&mut self, val: &T) {
+        if let (false, Some(copy)) = (self.checkpoints.is_empty(), self.copy) {
+            self.undo.push(Undo::Pop(copy(val)));
+        }
+    }
+
+    // Closes `checkpoint` and the ones taken after it, returning the changes
+    // made since.
+    fn close(&mut self, checkpoint: &Checkpoint) -> Vec<Undo<T>> {
+        if self.checkpoints.get(checkpoint.level) != Some(&checkpoint.journal_len) {
+            panic!("Неверная контрольная точка");
+        }
+        self.checkpoints.truncate(checkpoint.level);
+        if self.checkpoints.is_empty() {
+            return std::mem::take(&mut self.undo);
+        }
+        self.undo.split_off(checkpoint.journal_len)
+    }
+}
+
+impl<T: Clone + Ord> MinStack<T> {
+    // Checkpoints nest: rolling back to or releasing a checkpoint also
+    // discards the ones taken after it.
+    pub fn checkpoint(&mut self) -> Checkpoint {
+        let journal = &mut self.journal;
+        journal.copy = Some(T::clone);
+        journal.checkpoints.push(journal.undo.len());
+        Checkpoint {
+            level: journal.checkpoints.len() - 1,
+            journal_len: journal.undo.len(),
+        }
+    }
+
+    // Undoes every push and pop since `checkpoint`, in time proportional to
+    // their number.
+    pub fn rollback(&mut self, checkpoint: Checkpoint) {
+        for undo in self.journal.close(&checkpoint).into_iter().rev() {
+            match undo {
+                Undo::Push => {
+                    self.take();
+                }
+                Undo::Pop(val) => self.put(val),
+            }
+        }
+    }
+
+    // Keeps the changes made since `checkpoint`.
+    pub fn release(&mut self, checkpoint: Checkpoint) {
+        self.journal.close(&checkpoint);
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+// Returned by `RollbackMinQueue::checkpoint`.
+#[derive(Debug, PartialEq, Eq)]
+pub struct QueueCheckpoint {
+    level: usize,
+    head: usize,
+    tail: usize,
+}
+
+// Elements are numbered in push order and the queue is `[head, tail)`.
+// Values at indices below `tail` never change, so a checkpoint only has to
+// remember `head` and `tail`, and popped values are kept while a checkpoint
+// may bring them back. The minimum is kept as in the two-stack queue: the
+// front part `[head, mid)` knows the minima of its suffixes and the back part
+// `[mid, tail)` those of its prefixes. Moving the back to the front changes
+// no values, so rolling back keeps the move unless it took some of the undone
+// pushes along.
+pub struct RollbackMinQueue<T> {
+    // `values[i - offset]` is the value number `i`.
+    values: VecDeque<T>,
+    offset: usize,
+    head: usize,
+    mid: usize,
+    tail: usize,
+    // Minimum of `[i, mid)` at `i - head`.
+    front_mins: VecDeque<T>,
+    // Minimum of `[mid, j]` at `j - mid`.
+    back_mins: Vec<T>,
+    // `head` and `tail` at the open checkpoints, oldest first.
+    checkpoints: Vec<(usize, usize)>,
+}
+
+impl<T: Clone + Ord> Default for RollbackMinQueue<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Clone + Ord> RollbackMinQueue<T> {
+    pub fn new() -> Self {
+        RollbackMinQueue {
+            values: VecDeque::new(),
+            offset: 0,
+            head: 0,
+            mid: 0,
+            tail: 0,
+            front_mins: VecDeque::new(),
+            back_mins: vec![],
+            checkpoints: vec![],
+        }
+    }
+
+    pub fn push(&mut self, val: T) {
+        let min = match self.back_mins.last() {
+            Some(min) if *min < val => min.clone(),
+            _ => val.clone(),
+        };
+        self.back_mins.push(min);
+        self.values.push_back(val);
+        self.tail += 1;
+    }
+
+    pub fn pop(&mut self) -> Option<T> {
+        if self.head == self.tail {
+            return None;
+        }
+        if self.head == self.mid {
+            self.rebuild();
+        }
+        self.front_mins.pop_front();
+        self.head += 1;
+        if self.checkpoints.is_empty() {
+            self.offset += 1;
+            self.values.pop_front()
+        } else {
+            Some(self.values[self.head - 1 - self.offset].clone())
+        }
+    }
+
+    // Makes `[head, tail)` the front part.
+    fn rebuild(&mut self) {
+        self.front_mins.clear();
+        for i in (self.head..self.tail).rev() {
+            let val = &self.values[i - self.offset];
+            let min = match self.front_mins.front() {
+                Some(min) if min < val => min.clone(),
+                _ => val.clone(),
+            };
+            self.front_mins.push_front(min);
+        }
+        self.back_mins.clear();
+        self.mid = self.tail;
+    }
+
+    pub fn front(&self) -> Option<&T> {
+        self.get(self.head)
+    }
+
+    pub fn back(&self) -> Option<&T> {
+        self.get(self.tail.wrapping_sub(1))
+    }
+
+    fn get(&self, index: usize) -> Option<&T> {
+        if self.head == self.tail {
+            return None;
+        }
+        self.values.get(index - self.offset)
+    }
+
+    pub fn min(&self) -> Option<&T> {
+        match (self.front_mins.front(), self.back_mins.last()) {
+            (None, None) => None,
+            (Some(x), None) | (None, Some(x)) => Some(x),
+            (Some(x), Some(y)) => Some(x.min(y)),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.tail - self.head
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.head == self.tail
+    }
+
+    // Checkpoints nest like those of `MinStack`.
+    pub fn checkpoint(&mut self) -> QueueCheckpoint {
+        self.checkpoints.push((self.head, self.tail));
+        QueueCheckpoint {
+            level: self.checkpoints.len() - 1,
+            head: self.head,
+            tail: self.tail,
+        }
+    }
+
+    // Undoes every push and pop since `checkpoint`, in time proportional to
+    // their number, unless some of the undone pushes were already moved to
+    // the front part. Then the front is rebuilt in time proportional to the
+    // length of the queue, and nothing pays for it: with nested checkpoints
+    // every rollback may cost that much.
+    pub fn rollback(&mut self, checkpoint: QueueCheckpoint) {
+        self.close(&checkpoint);
+        self.values.truncate(checkpoint.tail - self.offset);
+        self.tail = checkpoint.tail;
+        if checkpoint.tail < self.mid {
+            self.head = checkpoint.head;
+            self.rebuild();
+        } else {
+            self.back_mins.truncate(checkpoint.tail - self.mid);
+            while self.head > checkpoint.head {
+                self.head -= 1;
+                let val = &self.values[self.head - self.offset];
+                let min = match self.front_mins.front() {
+                    Some(min) if min < val => min.clone(),
+                    _ => val.clone(),
+                };
+                self.front_mins.push_front(min);
+            }
+        }
+        self.forget_popped();
+    }
+
+    // Keeps the changes made since `checkpoint`.
+    pub fn release(&mut self, checkpoint: QueueCheckpoint) {
+        self.close(&checkpoint);
+        self.forget_popped();
+    }
+
+    fn close(&mut self, checkpoint: &QueueCheckpoint) {
+        let expected = (checkpoint.head, checkpoint.tail);
+        if self.checkpoints.get(checkpoint.level) != Some(&expected) {
+            panic!("Неверная контрольная точка");
+        }
+        self.checkpoints.truncate(checkpoint.level);
+    }
+
+    fn forget_popped(&mut self) {
+        if self.checkpoints.is_empty() {
+            self.values.drain(..self.head - self.offset);
+            self.offset = self.head;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{MinStack, RollbackMinQueue};
+    use crate::intro::minqueue::counted::{work, Counted};
+    use rand::Rng;
+    use std::collections::VecDeque;
+
+    #[test]
+    fn stack_rollback() {
+        let mut stack = MinStack::new();
+        for value in [4, 2, 6] {
+            stack.push(value);
+        }
+        let outer = stack.checkpoint();
+        assert_eq!(stack.pop(), Some(6));
+        assert_eq!(stack.pop(), Some(2));
+        stack.push(1);
+        let inner = stack.checkpoint();
+        stack.push(0);
+        assert_eq!(stack.min(), Some(&0));
+        stack.rollback(inner);
+        assert_eq!(stack.min(), Some(&1));
+        stack.rollback(outer);
+        assert_eq!(stack.peek(), Some(&6));
+        assert_eq!(stack.len(), 3);
+        assert_eq!(stack.min(), Some(&2));
+        assert!(stack.journal.undo.is_empty());
+
+        let checkpoint = stack.checkpoint();
+        stack.pop();
+        stack.release(checkpoint);
+        assert_eq!(stack.peek(), Some(&2));
+        assert!(stack.journal.undo.is_empty());
+    }
+
+    #[test]
+    fn stack_rollback_cost() {
+        let mut stack = MinStack::new();
+        for value in 0..100000 {
+            stack.push(value);
+        }
+        for i in 0..1000 {
+            let checkpoint = stack.checkpoint();
+            for _ in 0..i % 10 {
+                stack.pop();
+            }
+            stack.push(-1);
+            assert_eq!(stack.journal.undo.len(), i % 10 + 1);
+            stack.rollback(checkpoint);
+        }
+        assert_eq!(stack.len(), 100000);
+        assert_eq!(stack.min(), Some(&0));
+    }
+
+    #[test]
+    #[should_panic]
+    fn stack_stale_checkpoint() {
+        let mut stack = MinStack::<i32>::new();
+        let outer = stack.checkpoint();
+        stack.push(1);
+        let inner = stack.checkpoint();
+        stack.release(outer);
+        stack.release(inner);
+    }
+
+    #[test]
+    fn it_works() {
+        let mut queue = RollbackMinQueue::new();
+        queue.push(3);
+        queue.push(1);
+        let checkpoint = queue.checkpoint();
+        assert_eq!(queue.pop(), Some(3));
+        assert_eq!(queue.pop(), Some(1));
+        queue.push(5);
+        assert_eq!(queue.min(), Some(&5));
+        queue.rollback(checkpoint);
+        assert_eq!(queue.len(), 2);
+        assert_eq!(queue.front(), Some(&3));
+        assert_eq!(queue.back(), Some(&1));
+        assert_eq!(queue.min(), Some(&1));
+
+        let checkpoint = queue.checkpoint();
+        queue.push(0);
+        queue.release(checkpoint);
+        assert_eq!(queue.min(), Some(&0));
+        assert_eq!(queue.pop(), Some(3));
+        assert_eq!(queue.values.len(), 2);
+    }
+
+    #[test]
+    #[should_panic]
+    fn stale_checkpoint() {
+        let mut queue = RollbackMinQueue::new();
+        let outer = queue.checkpoint();
+        queue.push(1);
+        let inner = queue.checkpoint();
+        queue.rollback(outer);
+        queue.rollback(inner);
+    }
+
+    // A depth-first search that remembers the queue at every level.
+    #[test]
+    fn compare_with_snapshots() {
+        let mut queue = RollbackMinQueue::new();
+        let mut naive = VecDeque::new();
+        let mut stack = vec![];
+        let mut rng = rand::thread_rng();
+        for _ in 0..20000 {
+            match rng.gen_range(0..8) {
+                0 | 1 => assert_eq!(queue.pop(), naive.pop_front()),
+                2 => {
+                    stack.push((queue.checkpoint(), naive.clone()));
+                }
+                3 if !stack.is_empty() => {
+                    let (checkpoint, snapshot) = stack.pop().unwrap();
+                    queue.rollback(checkpoint);
+                    naive = snapshot;
+                }
+                4 if !stack.is_empty() => {
+                    let (checkpoint, _) = stack.pop().unwrap();
+                    queue.release(checkpoint);
+                }
+                _ => {
+                    let value = rng.gen_range(0..1000);
+                    queue.push(value);
+                    naive.push_back(value);
+                }
+            }
+            assert_eq!(queue.len(), naive.len());
+            assert_eq!(queue.front(), naive.front());
+            assert_eq!(queue.back(), naive.back());
+            assert_eq!(queue.min(), naive.iter().min());
+        }
+    }
+
+    // Checkpoint, pop and roll back, over and over: the back is moved to the
+    // front only once.
+    #[test]
+    fn repeated_pop_and_rollback() {
+        const LEN: usize = 10000;
+        let mut queue = RollbackMinQueue::new();
+        for i in 0..LEN as u64 {
+            queue.push(Counted(i));
+        }
+        let mut total = 0;
+        for round in 0..2000 {
+            total += work(|| {
+                let checkpoint = queue.checkpoint();
+                assert_eq!(queue.pop(), Some(Counted(0)));
+                if round % 2 == 1 {
+                    queue.push(Counted(round));
+                    queue.pop();
+                }
+                queue.rollback(checkpoint);
+            });
+            assert_eq!(queue.len(), LEN);
+        }
+        assert!(total <= 2 * LEN + 10 * 2000, "{}", total);
+        assert_eq!(queue.min(), Some(&Counted(0)));
+    }
+
+    // The worst case of `rollback`: every checkpoint undoes a push that was
+    // already moved to the front, and each rollback rebuilds the whole front.
+    #[test]
+    fn rollback_after_move() {
+        const LEN: usize = 1000;
+        const DEPTH: usize = 100;
+        let mut queue = RollbackMinQueue::new();
+        for i in 0..LEN as u64 {
+            queue.push(Counted(i));
+        }
+        let checkpoints: Vec<_> = (0..DEPTH as u64)
+            .map(|i| {
+                let checkpoint = queue.checkpoint();
+                queue.push(Counted(LEN as u64 + i));
+                checkpoint
+            })
+            .collect();
+        assert_eq!(queue.pop(), Some(Counted(0)));
+        for (i, checkpoint) in checkpoints.into_iter().enumerate().rev() {
+            let cost = work(|| queue.rollback(checkpoint));
+            assert_eq!(queue.len(), LEN + i);
+            assert!(LEN + i <= cost && cost <= 2 * (LEN + i) + 2, "{}", cost);
+            assert_eq!(queue.min(), Some(&Counted(0)));
+        }
+    }
+}
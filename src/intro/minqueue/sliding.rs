@@ -0,0 +1,265 @@
+#![forbid(unsafe_code)]
+
+use std::collections::VecDeque;
+
+////////////////////////////////////////////////////////////////////////////////
+
+// Extreme elements over every full window of `window` consecutive items.
+// Positions are indices in the source iterator; of equal extremes the
+// earliest one is reported.
+pub trait SlidingExt: Iterator + Sized
+where
+    Self::Item: Clone,
+{
+    fn sliding_min(self, window: usize) -> impl Iterator<Item = Self::Item>
+    where
+        Self::Item: Ord,
+    {
+        self.sliding_min_position(window).map(|x| x.1)
+    }
+
+    fn sliding_max(self, window: usize) -> impl Iterator<Item = Self::Item>
+    where
+        Self::Item: Ord,
+    {
+        self.sliding_max_position(window).map(|x| x.1)
+    }
+
+    fn sliding_min_by_key<K: Ord, F: FnMut(&Self::Item) -> K>(
+        self,
+        window: usize,
+        key: F,
+    ) -> impl Iterator<Item = Self::Item> {
+        self.sliding_min_by_key_position(window, key).map(|x| x.1)
+    }
+
+    fn sliding_max_by_key<K: Ord, F: FnMut(&Self::Item) -> K>(
+        self,
+        window: usize,
+        key: F,
+    ) -> impl Iterator<Item = Self::Item> {
+        self.sliding_max_by_key_position(window, key).map(|x| x.1)
+    }
+
+    fn sliding_min_position(self, window: usize) -> impl Iterator<Item = (usize, Self::Item)>
+    where
+        Self::Item: Ord,
+    {
+        Extremes::new(self, window, Self::Item::clone, |back, new| back <= new)
+    }
+
+    fn sliding_max_position(self, window: usize) -> impl Iterator<Item = (usize, Self::Item)>
+    where
+        Self::Item: Ord,
+    {
+        Extremes::new(self, window, Self::Item::clone, |back, new| back >= new)
+    }
+
+    fn sliding_min_by_key_position<K: Ord, F: FnMut(&Self::Item) -> K>(
+        self,
+        window: usize,
+        key: F,
+    ) -> impl Iterator<Item = (usize, Self::Item)> {
+        Extremes::new(self, window, key, |back: &K, new: &K| back <= new)
+    }
+
+    fn sliding_max_by_key_position<K: Ord, F: FnMut(&Self::Item) -> K>(
+        self,
+        window: usize,
+        key: F,
+    ) -> impl Iterator<Item = (usize, Self::Item)> {
+        Extremes::new(self, window, key, |back: &K, new: &K| back >= new)
+    }
+}
+
+impl<I: Iterator> SlidingExt for I where I::Item: Clone {}
+
+////////////////////////////////////////////////////////////////////////////////
+
+// Monotonic deque: keys decrease (for minima) from back to front, and an
+// element is dropped once a newer one is strictly better, since it can never
+// be the answer for a later window. Equal keys stay, so the earliest of them
+// reaches the front first.
+struct Extremes<I: Iterator, K, F, C> {
+    iter: I,
+    window: usize,
+    position: usize,
+    deque: VecDeque<(usize, K, I::Item)>,
+    key: F,
+    // Whether the element at the back stays when one with the new key comes.
+    keep: C,
+}
+
+impl<I, K, F, C> Extremes<I, K, F, C>
+where
+    I: Iterator,
+    F: FnMut(&I::Item) -> K,
+    C: Fn(&K, &K) -> bool,
+{
+    fn new(iter: I, window: usize, key: F, keep: C) -> Self {
+        if window == 0 {
+            panic!("Неверный размер окна");
+        }
+        Extremes {
+            iter,
+            window,
+            position: 0,
+            deque: VecDeque::new(),
+            key,
+            keep,
+        }
+    }
+}
+
+impl<I, K, F, C> Iterator for Extremes<I, K, F, C>
+where
+    I: Iterator,
+    I::Item: Clone,
+    F: FnMut(&I::Item) -> K,
+    C: Fn(&K, &K) -> bool,
+{
+    type Item = (usize, I::Item);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let item = self.iter.next()?;
+            let key = (self.key)(&item);
+            while let Some(back) = self.deque.back() {
+                if (self.keep)(&back.1, &key) {
+                    break;
+                }
+                self.deque.pop_back();
+            }
+            self.deque.push_back((self.position, key, item));
+            self.position += 1;
+            if self.deque[0].0 + self.window < self.position {
+                self.deque.pop_front();
+            }
+            if self.position >= self.window {
+                let (position, _, item) = &self.deque[0];
+                return Some((*position, item.clone()));
+            }
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let missing = (self.window - 1).saturating_sub(self.position);
+        let (lower, upper) = self.iter.size_hint();
+        (
+            lower.saturating_sub(missing),
+            upper.map(|upper| upper.saturating_sub(missing)),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SlidingExt;
+    use rand::Rng;
+
+    #[test]
+    fn it_works() {
+        let data = [4, 2, 12, 3, 8, 5, 2, 7];
+        assert_eq!(
+            data.iter().copied().sliding_min(3).collect::<Vec<_>>(),
+            vec![2, 2, 3, 3, 2, 2]
+        );
+        assert_eq!(
+            data.iter().copied().sliding_max(3).collect::<Vec<_>>(),
+            vec![12, 12, 12, 8, 8, 7]
+        );
+        assert_eq!(
+            data.iter().sliding_min_position(4).collect::<Vec<_>>(),
+            vec![(1, &2), (1, &2), (3, &3), (6, &2), (6, &2)]
+        );
+        assert_eq!(
+            [1, 1, 1]
+                .into_iter()
+                .sliding_max_position(2)
+                .collect::<Vec<_>>(),
+            vec![(0, 1), (1, 1)]
+        );
+        assert_eq!(data.iter().sliding_min(9).count(), 0);
+        assert_eq!(data.iter().sliding_min(8).size_hint(), (1, Some(1)));
+
+        let words = ["pear", "fig", "banana", "kiwi", "plum"];
+        assert_eq!(
+            words
+                .iter()
+                .sliding_min_by_key(2, |word| word.len())
+                .collect::<Vec<_>>(),
+            vec![&"fig", &"fig", &"kiwi", &"kiwi"]
+        );
+        assert_eq!(
+            words
+                .iter()
+                .sliding_max_by_key(3, |word| word.len())
+                .collect::<Vec<_>>(),
+            vec![&"banana", &"banana", &"banana"]
+        );
+        assert_eq!(
+            words
+                .iter()
+                .sliding_min_by_key_position(2, |word| word.len())
+                .collect::<Vec<_>>(),
+            vec![(1, &"fig"), (1, &"fig"), (3, &"kiwi"), (3, &"kiwi")]
+        );
+        assert_eq!(
+            words
+                .iter()
+                .sliding_max_by_key_position(3, |word| word.len())
+                .collect::<Vec<_>>(),
+            vec![(2, &"banana"), (2, &"banana"), (2, &"banana")]
+        );
+    }
+
+    #[test]
+    fn compare_with_naive() {
+        let mut rng = rand::thread_rng();
+        for _ in 0..100 {
+            let data: Vec<i32> = (0..rng.gen_range(0..200))
+                .map(|_| rng.gen_range(0..20))
+                .collect();
+            let window = rng.gen_range(1..20);
+            let first = |extreme: &i32, w: &[i32]| w.iter().position(|x| x == extreme).unwrap();
+            let min: Vec<_> = data
+                .windows(window)
+                .enumerate()
+                .map(|(i, w)| {
+                    let min = *w.iter().min().unwrap();
+                    (i + first(&min, w), min)
+                })
+                .collect();
+            let max: Vec<_> = data
+                .windows(window)
+                .enumerate()
+                .map(|(i, w)| {
+                    let max = *w.iter().max().unwrap();
+                    (i + first(&max, w), max)
+                })
+                .collect();
+            let positions: Vec<_> = data.iter().copied().sliding_min_position(window).collect();
+            assert_eq!(positions, min);
+            let positions: Vec<_> = data.iter().copied().sliding_max_position(window).collect();
+            assert_eq!(positions, max);
+            let by_key: Vec<_> = data
+                .iter()
+                .copied()
+                .sliding_min_by_key(window, |x| -x)
+                .collect();
+            assert_eq!(by_key, max.iter().map(|x| x.1).collect::<Vec<_>>());
+            let by_key: Vec<_> = data
+                .iter()
+                .copied()
+                .sliding_max_by_key_position(window, |x| -x)
+                .collect();
+            assert_eq!(by_key, min);
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn empty_window() {
+        [1, 2, 3].iter().sliding_min(0).count();
+    }
+}
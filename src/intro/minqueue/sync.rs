@@ -0,0 +1,197 @@
+#![forbid(unsafe_code)]
+
+use super::MinQueue;
+use std::sync::{Condvar, Mutex, RwLock};
+use std::time::{Duration, Instant};
+
+////////////////////////////////////////////////////////////////////////////////
+
+// `MinQueue` behind a mutex, with a condition variable for the consumers.
+// The minimum is copied after every change into a separate lock, so reading
+// it never waits for the queue itself.
+pub struct SyncMinQueue<T> {
+    queue: Mutex<MinQueue<T>>,
+    not_empty: Condvar,
+    min: RwLock<Option<T>>,
+}
+
+impl<T: Clone + Ord> Default for SyncMinQueue<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Clone + Ord> SyncMinQueue<T> {
+    pub fn new() -> Self {
+        SyncMinQueue {
+            queue: Mutex::new(MinQueue::new()),
+            not_empty: Condvar::new(),
+            min: RwLock::new(None),
+        }
+    }
+
+    pub fn push(&self, val: T) {
+        let mut queue = self.queue.lock().unwrap();
+        queue.push(val);
+        self.update_min(&queue);
+        drop(queue);
+        self.not_empty.notify_one();
+    }
+
+    pub fn pop(&self) -> Option<T> {
+        let mut queue = self.queue.lock().unwrap();
+        let val = queue.pop();
+        self.update_min(&queue);
+        val
+    }
+
+    // Blocks until there is a value.
+    pub fn pop_wait(&self) -> T {
+        let queue = self.queue.lock().unwrap();
+        let mut queue = self
+            .not_empty
+            .wait_while(queue, |queue| queue.is_empty())
+            .unwrap();
+        let val = queue.pop().unwrap();
+        self.update_min(&queue);
+        val
+    }
+
+    // `None` if the queue stays empty for `timeout`. A timeout too long to
+    // reach is the same as no timeout at all.
+    pub fn pop_timeout(&self, timeout: Duration) -> Option<T> {
+        let Some(deadline) = Instant::now().checked_add(timeout) else {
+            return Some(self.pop_wait());
+        };
+        let mut queue = self.queue.lock().unwrap();
+        while queue.is_empty() {
+            let left = deadline.checked_duration_since(Instant::now())?;
+            queue = self.not_empty.wait_timeout(queue, left).unwrap().0;
+        }
+        let val = queue.pop();
+        self.update_min(&queue);
+        val
+    }
+
+    // Called under the queue lock, so the copies are made in the order of
+    // the changes.
+    fn update_min(&self, queue: &MinQueue<T>) {
+        *self.min.write().unwrap() = queue.min().cloned();
+    }
+
+    pub fn min(&self) -> Option<T> {
+        self.min.read().unwrap().clone()
+    }
+
+    pub fn len(&self) -> usize {
+        self.queue.lock().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.queue.lock().unwrap().is_empty()
+    }
+
+    pub fn into_inner(self) -> MinQueue<T> {
+        self.queue.into_inner().unwrap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SyncMinQueue;
+    use std::sync::Arc;
+    use std::thread;
+    use std::time::{Duration, Instant};
+
+    #[test]
+    fn it_works() {
+        let queue = SyncMinQueue::new();
+        assert_eq!(queue.min(), None);
+        queue.push(3);
+        queue.push(1);
+        queue.push(2);
+        assert_eq!(queue.min(), Some(1));
+        assert_eq!(queue.pop(), Some(3));
+        assert_eq!(queue.pop_wait(), 1);
+        assert_eq!(queue.min(), Some(2));
+        assert_eq!(queue.pop_timeout(Duration::ZERO), Some(2));
+        assert_eq!(queue.min(), None);
+        assert!(queue.is_empty());
+        assert_eq!(queue.pop(), None);
+    }
+
+    #[test]
+    fn pop_timeout_expires() {
+        let queue = SyncMinQueue::<i32>::new();
+        let start = Instant::now();
+        assert_eq!(queue.pop_timeout(Duration::from_millis(50)), None);
+        assert!(start.elapsed() >= Duration::from_millis(50));
+    }
+
+    #[test]
+    fn pop_timeout_without_deadline() {
+        let queue = Arc::new(SyncMinQueue::new());
+        queue.push(1);
+        assert_eq!(queue.pop_timeout(Duration::MAX), Some(1));
+        let producer = {
+            let queue = queue.clone();
+            thread::spawn(move || {
+                thread::sleep(Duration::from_millis(20));
+                queue.push(2);
+            })
+        };
+        assert_eq!(queue.pop_timeout(Duration::MAX), Some(2));
+        producer.join().unwrap();
+    }
+
+    #[test]
+    fn producers_and_consumer() {
+        const PRODUCERS: u64 = 4;
+        const COUNT: u64 = 10000;
+        let queue = Arc::new(SyncMinQueue::new());
+        let producers: Vec<_> = (0..PRODUCERS)
+            .map(|p| {
+                let queue = queue.clone();
+                thread::spawn(move || {
+                    for i in 0..COUNT {
+                        queue.push(p * COUNT + i);
+                    }
+                })
+            })
+            .collect();
+        let consumer = {
+            let queue = queue.clone();
+            thread::spawn(move || {
+                let mut last = vec![None; PRODUCERS as usize];
+                let mut sum = 0;
+                for _ in 0..PRODUCERS * COUNT {
+                    let val = queue.pop_wait();
+                    // Values of one producer come out in push order.
+                    let p = (val / COUNT) as usize;
+                    assert!(last[p] < Some(val));
+                    last[p] = Some(val);
+                    sum += val;
+                }
+                sum
+            })
+        };
+        let reader = {
+            let queue = queue.clone();
+            thread::spawn(move || {
+                for _ in 0..1000 {
+                    if let Some(min) = queue.min() {
+                        assert!(min < PRODUCERS * COUNT);
+                    }
+                }
+            })
+        };
+        for producer in producers {
+            producer.join().unwrap();
+        }
+        reader.join().unwrap();
+        let total = PRODUCERS * COUNT;
+        assert_eq!(consumer.join().unwrap(), total * (total - 1) / 2);
+        assert!(queue.is_empty());
+        assert_eq!(queue.min(), None);
+    }
+}
@@ -0,0 +1,247 @@
+#![forbid(unsafe_code)]
+
+use super::MinQueue;
+use std::cell::Cell;
+use std::rc::Rc;
+use std::time::{Duration, Instant};
+
+////////////////////////////////////////////////////////////////////////////////
+
+// Time since some fixed moment chosen by the clock.
+pub trait Clock {
+    fn now(&self) -> Duration;
+}
+
+pub struct SystemClock {
+    start: Instant,
+}
+
+impl SystemClock {
+    pub fn new() -> Self {
+        SystemClock {
+            start: Instant::now(),
+        }
+    }
+}
+
+impl Default for SystemClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Clock for SystemClock {
+    fn now(&self) -> Duration {
+        self.start.elapsed()
+    }
+}
+
+// Moves only when told to. Clones share the same time, so a test can keep one
+// and hand the other to a queue.
+#[derive(Clone, Debug, Default)]
+pub struct ManualClock {
+    now: Rc<Cell<Duration>>,
+}
+
+impl ManualClock {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn set(&self, now: Duration) {
+        self.now.set(now);
+    }
+
+    pub fn advance(&self, by: Duration) {
+        self.now.set(self.now.get() + by);
+    }
+}
+
+impl Clock for ManualClock {
+    fn now(&self) -> Duration {
+        self.now.get()
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+// Ordered by value first, so the minimum of the queue is the minimal value.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+struct Timed<T> {
+    value: T,
+    time: Duration,
+}
+
+pub struct TimedMinQueue<T, C = SystemClock> {
+    queue: MinQueue<Timed<T>>,
+    window: Duration,
+    clock: C,
+    last: Duration,
+}
+
+impl<T: Clone + Ord> TimedMinQueue<T> {
+    pub fn new(window: Duration) -> Self {
+        Self::with_clock(window, SystemClock::new())
+    }
+}
+
+impl<T: Clone + Ord, C: Clock> TimedMinQueue<T, C> {
+    pub fn with_clock(window: Duration, clock: C) -> Self {
+        TimedMinQueue {
+            queue: MinQueue::new(),
+            window,
+            clock,
+            last: Duration::ZERO,
+        }
+    }
+
+    pub fn clock(&self) -> &C {
+        &self.clock
+    }
+
+    pub fn window(&self) -> Duration {
+        self.window
+    }
+
+    // Timestamps never decrease, even if the clock does, so the oldest
+    // entries are always at the front.
+    pub fn push(&mut self, val: T) {
+        self.last = self.last.max(self.clock.now());
+        self.queue.push(Timed {
+            value: val,
+            time: self.last,
+        });
+    }
+
+    pub fn pop(&mut self) -> Option<(T, Duration)> {
+        self.queue.pop().map(|x| (x.value, x.time))
+    }
+
+    // Drops the entries pushed before `cutoff`, returning how many.
+    pub fn evict_older_than(&mut self, cutoff: Duration) -> usize {
+        let mut count = 0;
+        while self.queue.front().is_some_and(|x| x.time < cutoff) {
+            self.queue.pop();
+            count += 1;
+        }
+        count
+    }
+
+    // Drops the entries that are older than the window.
+    pub fn expire(&mut self) -> usize {
+        let cutoff = self.clock.now().saturating_sub(self.window);
+        self.evict_older_than(cutoff)
+    }
+
+    pub fn front(&self) -> Option<(&T, Duration)> {
+        self.queue.front().map(|x| (&x.value, x.time))
+    }
+
+    // Minimum of the entries currently stored, including expired ones that
+    // have not been evicted yet.
+    pub fn min(&self) -> Option<&T> {
+        self.queue.min().map(|x| &x.value)
+    }
+
+    // Minimum over the window ending now.
+    pub fn window_min(&mut self) -> Option<&T> {
+        self.expire();
+        self.min()
+    }
+
+    pub fn len(&self) -> usize {
+        self.queue.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.queue.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Clock, ManualClock, SystemClock, TimedMinQueue};
+    use rand::Rng;
+    use std::collections::VecDeque;
+    use std::time::Duration;
+
+    fn secs(secs: u64) -> Duration {
+        Duration::from_secs(secs)
+    }
+
+    #[test]
+    fn it_works() {
+        let clock = ManualClock::new();
+        let mut queue = TimedMinQueue::with_clock(secs(30), clock.clone());
+        assert_eq!(queue.window_min(), None);
+        queue.push(120);
+        clock.advance(secs(10));
+        queue.push(80);
+        clock.advance(secs(10));
+        queue.push(95);
+        assert_eq!(queue.window_min(), Some(&80));
+        assert_eq!(queue.front(), Some((&120, secs(0))));
+
+        clock.set(secs(35));
+        assert_eq!(queue.window_min(), Some(&80));
+        assert_eq!(queue.len(), 2);
+        clock.set(secs(45));
+        assert_eq!(queue.min(), Some(&80));
+        assert_eq!(queue.window_min(), Some(&95));
+        assert_eq!(queue.pop(), Some((95, secs(20))));
+        assert!(queue.is_empty());
+
+        clock.set(secs(100));
+        queue.push(1);
+        clock.set(secs(90));
+        queue.push(2);
+        assert_eq!(queue.pop(), Some((1, secs(100))));
+        assert_eq!(queue.pop(), Some((2, secs(100))));
+    }
+
+    #[test]
+    fn evict_older_than() {
+        let clock = ManualClock::new();
+        let mut queue = TimedMinQueue::with_clock(secs(5), clock.clone());
+        for value in [3, 1, 4, 1, 5] {
+            queue.push(value);
+            clock.advance(secs(1));
+        }
+        assert_eq!(queue.evict_older_than(secs(2)), 2);
+        assert_eq!(queue.evict_older_than(secs(2)), 0);
+        assert_eq!(queue.min(), Some(&1));
+        assert_eq!(queue.evict_older_than(secs(4)), 2);
+        assert_eq!(queue.min(), Some(&5));
+        assert_eq!(queue.evict_older_than(secs(10)), 1);
+        assert_eq!(queue.min(), None);
+    }
+
+    #[test]
+    fn compare_with_naive() {
+        let clock = ManualClock::new();
+        let window = secs(30);
+        let mut queue = TimedMinQueue::with_clock(window, clock.clone());
+        let mut naive = VecDeque::new();
+        let mut rng = rand::thread_rng();
+        for _ in 0..5000 {
+            clock.advance(Duration::from_millis(rng.gen_range(0..3000)));
+            let value = rng.gen_range(0..1000);
+            queue.push(value);
+            naive.push_back((value, clock.now()));
+            let cutoff = clock.now().saturating_sub(window);
+            naive.retain(|&(_, time)| time >= cutoff);
+            assert_eq!(queue.window_min(), naive.iter().map(|x| &x.0).min());
+            assert_eq!(queue.len(), naive.len());
+        }
+    }
+
+    #[test]
+    fn system_clock() {
+        let clock = SystemClock::new();
+        let first = clock.now();
+        assert!(clock.now() >= first);
+        let mut queue = TimedMinQueue::new(secs(60));
+        queue.push(7);
+        assert_eq!(queue.window_min(), Some(&7));
+    }
+}